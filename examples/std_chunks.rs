@@ -0,0 +1,52 @@
+//! Chunked body decoding from standard input (blocking).
+//!
+//! Demonstrates [`HttpChunksIter`], which yields each decoded chunk
+//! of a body sent using chunked transfer coding, as soon as it is
+//! received.
+//!
+//! # Usage
+//!
+//! ```sh
+//! printf '5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n' | cargo run --example std_chunks
+//! ```
+
+use std::io::{self, Read, Stdin, Write};
+
+use io_http::rfc9112::{chunk::HttpChunksRead, chunk_iter::HttpChunksIter};
+use io_socket::coroutines::read::SocketRead;
+
+/// Read-only stream over the standard input.
+struct StdinStream(Stdin);
+
+impl Read for StdinStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for StdinStream {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let read = HttpChunksRead::new(SocketRead::default());
+    let mut chunks = HttpChunksIter::new(read, StdinStream(io::stdin()));
+
+    for (i, chunk) in chunks.by_ref().enumerate() {
+        let chunk = chunk.unwrap();
+        println!("chunk {i} ({} bytes):", chunk.len());
+        println!("{}", String::from_utf8_lossy(&chunk));
+    }
+
+    for (name, value) in chunks.trailers() {
+        println!("trailer {name}: {value}");
+    }
+}
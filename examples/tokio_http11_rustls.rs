@@ -65,6 +65,7 @@ async fn main() {
                     url = new_url;
                     break;
                 }
+                Http11SendResult::Tunnel { .. } => unreachable!("CONNECT request not sent"),
             }
         }
     };
@@ -2,6 +2,8 @@
 #![doc = include_str!("../README.md")]
 #![no_std]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 pub mod rfc1945;
 pub mod rfc6750;
@@ -9,3 +11,38 @@ pub mod rfc7617;
 pub mod rfc8615;
 pub mod rfc9110;
 pub mod rfc9112;
+pub mod sse;
+
+#[cfg(feature = "log")]
+pub(crate) use log::{info, trace};
+
+/// No-op replacement for `log::info` when the `log` feature is
+/// disabled. Arguments, key-value fields included, are still
+/// type-checked.
+#[cfg(not(feature = "log"))]
+macro_rules! info {
+    ($($key:ident = $value:expr),+; $($arg:tt)+) => {{
+        $(let _ = &$value;)+
+        let _ = format_args!($($arg)+);
+    }};
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+
+/// No-op replacement for `log::trace` when the `log` feature is
+/// disabled. Arguments, key-value fields included, are still
+/// type-checked.
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($key:ident = $value:expr),+; $($arg:tt)+) => {{
+        $(let _ = &$value;)+
+        let _ = format_args!($($arg)+);
+    }};
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) use {info, trace};
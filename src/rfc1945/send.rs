@@ -22,18 +22,20 @@ use io_socket::{
     coroutines::{read::*, read_exact::*, read_to_end::*, write::*},
     io::{SocketInput, SocketOutput},
 };
-use log::{Level, info, log_enabled, trace};
 use thiserror::Error;
 use url::Url;
 
 use crate::{
+    info,
     rfc1945::version::HTTP_10,
     rfc9110::{
-        headers::{CONNECTION, CONTENT_LENGTH, LOCATION},
+        headers::{CONTENT_LENGTH, is_token},
         request::HttpRequest,
         response::{HttpResponse, ResponseBuilder},
         status::StatusCode,
     },
+    rfc9112::connection::keep_alive,
+    trace,
 };
 
 const CR: u8 = b'\r';
@@ -52,6 +54,12 @@ pub enum Http10SendError {
     /// The HTTP response headers could not be parsed.
     #[error("Parse HTTP response headers error: {0}")]
     ParseResponseHeaders(httparse::Error),
+    /// The request method is not a valid token.
+    #[error("Invalid HTTP method {0:?}")]
+    InvalidMethod(String),
+    /// The request has trailer fields, which HTTP/1.0 cannot send.
+    #[error("Cannot send trailer fields with HTTP/1.0")]
+    TrailersNotSupported,
 
     #[error(transparent)]
     SocketRead(#[from] SocketReadError),
@@ -217,6 +225,22 @@ impl Http10Send {
                     let req = self.request.as_ref().unwrap();
                     trace!("HTTP/1.0 request: {req:?}");
 
+                    // a method that is not a token would corrupt the
+                    // request line (RFC 9110 §9.1)
+                    if !is_token(&req.method) {
+                        return Http10SendResult::Err {
+                            err: Http10SendError::InvalidMethod(req.method.clone()),
+                        };
+                    }
+
+                    // trailers require chunked transfer coding, which
+                    // HTTP/1.0 lacks: fail rather than drop them
+                    if !req.trailers.is_empty() {
+                        return Http10SendResult::Err {
+                            err: Http10SendError::TrailersNotSupported,
+                        };
+                    }
+
                     let mut bytes = Vec::new();
 
                     bytes.extend(req.method.as_bytes());
@@ -316,7 +340,8 @@ impl Http10Send {
                         }
                     };
 
-                    if log_enabled!(Level::Trace) {
+                    #[cfg(feature = "log")]
+                    if log::log_enabled!(log::Level::Trace) {
                         let h = String::from_utf8_lossy(&headers[..n]);
                         trace!("HTTP/1.0 response headers:\n{h}");
                     }
@@ -330,15 +355,17 @@ impl Http10Send {
                         response.status = Some(StatusCode(code));
                     }
 
+                    if let Some(reason) = parsed.reason {
+                        response.reason = reason.into();
+                    }
+
                     for header in parsed.headers {
                         response.header(header.name, header.value);
                     }
 
                     let body: Vec<u8> = headers.drain(n..).collect();
 
-                    if let Some(conn) = response.get_header(CONNECTION) {
-                        self.keep_alive = conn.eq_ignore_ascii_case("keep-alive");
-                    }
+                    self.keep_alive = keep_alive(&response.version, &response.headers);
 
                     if no_content {
                         break Http10SendResult::Ok {
@@ -407,21 +434,18 @@ impl Http10Send {
 /// [`Http10SendResult::Redirect`]; otherwise emits [`Http10SendResult::Ok`].
 fn finish(request: HttpRequest, response: HttpResponse, keep_alive: bool) -> Http10SendResult {
     if response.status.is_redirection() {
-        if let Some(location) = response.header(LOCATION) {
-            if let Ok(url) = request.url.join(location) {
-                let same_scheme = request.url.scheme() == url.scheme();
-                let same_host =
-                    request.url.host() == url.host() && request.url.port() == url.port();
-                let same_origin = same_scheme && same_host;
-
-                return Http10SendResult::Redirect {
-                    url,
-                    request,
-                    response,
-                    keep_alive,
-                    same_origin,
-                };
-            }
+        if let Some(url) = response.location(&request.url) {
+            let same_scheme = request.url.scheme() == url.scheme();
+            let same_host = request.url.host() == url.host() && request.url.port() == url.port();
+            let same_origin = same_scheme && same_host;
+
+            return Http10SendResult::Redirect {
+                url,
+                request,
+                response,
+                keep_alive,
+                same_origin,
+            };
         }
     }
     Http10SendResult::Ok {
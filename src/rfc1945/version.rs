@@ -1,4 +1,8 @@
-//! HTTP/1.0 version string constant (RFC 1945 §3.1).
+//! HTTP/1.0 and HTTP/0.9 version string constants (RFC 1945 §3.1).
 
 /// HTTP/1.0 version token as it appears on the wire.
 pub const HTTP_10: &str = "HTTP/1.0";
+
+/// HTTP/0.9 version token, set on responses sent without status line
+/// nor headers (RFC 1945 §3.1).
+pub const HTTP_09: &str = "HTTP/0.9";
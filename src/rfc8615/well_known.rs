@@ -133,6 +133,7 @@ impl WellKnown {
                 request,
                 response,
                 keep_alive,
+                ..
             } => WellKnownResult::Ok {
                 request,
                 response,
@@ -140,6 +141,16 @@ impl WellKnown {
                 same_origin: true,
                 redirect_url: None,
             },
+            // a `.well-known` discovery never sends CONNECT requests
+            Http11SendResult::Tunnel {
+                request, response, ..
+            } => WellKnownResult::Ok {
+                request,
+                response,
+                keep_alive: false,
+                same_origin: true,
+                redirect_url: None,
+            },
             Http11SendResult::Redirect {
                 url,
                 request,
@@ -0,0 +1,377 @@
+//! Authentication challenges (RFC 9110 §11).
+//!
+//! Servers ask for credentials by sending one or more challenges in
+//! the `WWW-Authenticate` (or `Proxy-Authenticate`) header, each
+//! made of an authentication scheme followed by either a `token68`
+//! or a list of parameters:
+//!
+//! ```text
+//! WWW-Authenticate: Newauth realm="apps", type=1, Basic realm="simple"
+//! ```
+//!
+//! [`parse_challenges`] decomposes such a value into [`Challenge`]s,
+//! the building block of any authentication flow.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// An authentication challenge (RFC 9110 §11.2).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Challenge {
+    /// The authentication scheme, as sent (e.g. `Basic`).
+    pub scheme: String,
+    /// The `token68` sent instead of parameters, if any.
+    pub token68: Option<String>,
+    /// The parameters, as `(name, value)` pairs (names stored in
+    /// lowercase, quoted values unescaped).
+    pub params: Vec<(String, String)>,
+}
+
+impl Challenge {
+    /// Returns whether the challenge uses the given scheme, compared
+    /// case-insensitively.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// Returns the value of the first parameter with the given name,
+    /// compared case-insensitively.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parses the challenges of a `WWW-Authenticate` or
+/// `Proxy-Authenticate` value.
+///
+/// Several challenges can be sent in the same value, separated by
+/// commas, which may also appear inside quoted parameter values.
+/// Malformed parts are skipped, so that the challenges around them
+/// can still be used.
+///
+/// # Example
+///
+/// ```rust
+/// use io_http::rfc9110::auth::parse_challenges;
+///
+/// let value = r#"Digest realm="a, b", qop="auth,auth-int", Basic realm="c""#;
+/// let challenges = parse_challenges(value);
+///
+/// assert_eq!(challenges.len(), 2);
+/// assert_eq!(challenges[0].param("realm"), Some("a, b"));
+/// assert_eq!(challenges[0].param("qop"), Some("auth,auth-int"));
+/// assert!(challenges[1].is_scheme("basic"));
+/// ```
+pub fn parse_challenges(value: &str) -> Vec<Challenge> {
+    let mut parser = Parser {
+        bytes: value.as_bytes(),
+        pos: 0,
+    };
+    let mut challenges = Vec::new();
+
+    loop {
+        parser.skip_separators();
+
+        if parser.peek().is_none() {
+            break challenges;
+        }
+
+        let scheme = parser.token();
+
+        if scheme.is_empty() {
+            // not a challenge, skip to the next list element
+            parser.skip_element();
+            continue;
+        }
+
+        let mut challenge = Challenge {
+            scheme: scheme.to_string(),
+            ..Default::default()
+        };
+
+        parser.skip_ows();
+
+        if parser.is_param_ahead() {
+            loop {
+                let (name, value) = parser.param();
+                challenge.params.push((name.to_ascii_lowercase(), value));
+
+                // a token not followed by `=` starts a new challenge
+                parser.skip_separators();
+
+                if !parser.is_param_ahead() {
+                    break;
+                }
+            }
+        } else if let Some(token68) = parser.token68() {
+            challenge.token68 = Some(token68.to_string());
+        }
+
+        challenges.push(challenge);
+    }
+}
+
+/// Returns whether the given byte is a token character (RFC 9110
+/// §5.6.2).
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// Returns whether the given byte is a `token68` character, trailing
+/// `=` excluded (RFC 9110 §11.2).
+fn is_token68_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~+/".contains(&b)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespace and empty list elements.
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b',')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the current list element, up to the next comma.
+    fn skip_element(&mut self) {
+        while !matches!(self.peek(), None | Some(b',')) {
+            self.pos += 1;
+        }
+    }
+
+    fn token(&mut self) -> &'a str {
+        let start = self.pos;
+
+        while self.peek().is_some_and(is_tchar) {
+            self.pos += 1;
+        }
+
+        // token characters are ASCII
+        core::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default()
+    }
+
+    /// Returns whether an `auth-param` follows: a token, then `=`,
+    /// then a value. The value cannot start with `=`, which tells
+    /// parameters from a `token68` with padding.
+    fn is_param_ahead(&self) -> bool {
+        let mut ahead = Parser {
+            bytes: self.bytes,
+            pos: self.pos,
+        };
+
+        if ahead.token().is_empty() {
+            return false;
+        }
+
+        ahead.skip_ows();
+
+        if ahead.peek() != Some(b'=') {
+            return false;
+        }
+
+        ahead.pos += 1;
+        ahead.skip_ows();
+
+        !matches!(ahead.peek(), None | Some(b'=' | b','))
+    }
+
+    /// Parses an `auth-param`, which must be ahead.
+    fn param(&mut self) -> (&'a str, String) {
+        let name = self.token();
+        self.skip_ows();
+        self.pos += 1;
+        self.skip_ows();
+
+        let value = if self.peek() == Some(b'"') {
+            self.quoted_string()
+        } else {
+            self.token().to_string()
+        };
+
+        self.skip_ows();
+        (name, value)
+    }
+
+    /// Parses a quoted string, unescaping quoted pairs (RFC 9110
+    /// §5.6.4). An unterminated string ends with the value.
+    fn quoted_string(&mut self) -> String {
+        let mut value = Vec::new();
+        self.pos += 1;
+
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    if let Some(b) = self.peek() {
+                        self.pos += 1;
+                        value.push(b);
+                    }
+                }
+                b => value.push(b),
+            }
+        }
+
+        String::from_utf8_lossy(&value).into_owned()
+    }
+
+    /// Parses a `token68`, if it is the whole list element.
+    fn token68(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+
+        while self.peek().is_some_and(is_token68_char) {
+            self.pos += 1;
+        }
+
+        if self.pos == start {
+            return None;
+        }
+
+        while self.peek() == Some(b'=') {
+            self.pos += 1;
+        }
+
+        let end = self.pos;
+        self.skip_ows();
+
+        if !matches!(self.peek(), None | Some(b',')) {
+            // neither a token68 nor parameters, skip to the next
+            // list element
+            self.skip_element();
+            return None;
+        }
+
+        core::str::from_utf8(&self.bytes[start..end]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn params(challenge: &Challenge) -> Vec<(&str, &str)> {
+        challenge
+            .params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn basic_realm() {
+        let challenges = parse_challenges(r#"Basic realm="simple""#);
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(challenges[0].scheme, "Basic");
+        assert_eq!(challenges[0].token68, None);
+        assert_eq!(params(&challenges[0]), [("realm", "simple")]);
+    }
+
+    /// Test case from RFC 9110 §11.6.1.
+    #[test]
+    fn multiple_challenges_with_quoted_commas() {
+        let value =
+            r#"Newauth realm="apps", type=1, title="Login to \"apps\"", Basic realm="simple""#;
+        let challenges = parse_challenges(value);
+
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].scheme, "Newauth");
+        assert_eq!(
+            params(&challenges[0]),
+            [
+                ("realm", "apps"),
+                ("type", "1"),
+                ("title", r#"Login to "apps""#)
+            ]
+        );
+        assert_eq!(challenges[1].scheme, "Basic");
+        assert_eq!(params(&challenges[1]), [("realm", "simple")]);
+    }
+
+    #[test]
+    fn digest_with_quoted_list() {
+        let value =
+            r#"Digest realm="a, b",qop="auth,auth-int" , nonce=abc123, algorithm = SHA-256"#;
+        let challenges = parse_challenges(value);
+
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(
+            params(&challenges[0]),
+            [
+                ("realm", "a, b"),
+                ("qop", "auth,auth-int"),
+                ("nonce", "abc123"),
+                ("algorithm", "SHA-256")
+            ]
+        );
+    }
+
+    #[test]
+    fn bearer_error_then_basic() {
+        let value = r#"Bearer realm="example", error="invalid_token", Basic realm="x""#;
+        let challenges = parse_challenges(value);
+
+        assert_eq!(challenges.len(), 2);
+        assert!(challenges[0].is_scheme("bearer"));
+        assert_eq!(challenges[0].param("ERROR"), Some("invalid_token"));
+        assert!(challenges[1].is_scheme("BASIC"));
+    }
+
+    #[test]
+    fn token68_with_padding() {
+        let challenges = parse_challenges("Negotiate YII=, Basic realm=x");
+
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].token68.as_deref(), Some("YII="));
+        assert!(challenges[0].params.is_empty());
+        assert_eq!(challenges[1].param("realm"), Some("x"));
+    }
+
+    #[test]
+    fn schemes_without_params() {
+        let challenges = parse_challenges("Negotiate, ,NTLM");
+        let schemes: Vec<&str> = challenges.iter().map(|c| c.scheme.as_str()).collect();
+        assert_eq!(schemes, ["Negotiate", "NTLM"]);
+    }
+
+    #[test]
+    fn malformed_parts_skipped() {
+        let challenges = parse_challenges(r#"="x", Basic realm="unterminated"#);
+
+        assert_eq!(
+            challenges,
+            vec![Challenge {
+                scheme: "Basic".into(),
+                token68: None,
+                params: vec![("realm".into(), "unterminated".into())],
+            }]
+        );
+    }
+
+    #[test]
+    fn empty_value() {
+        assert!(parse_challenges("").is_empty());
+        assert!(parse_challenges(" , ").is_empty());
+    }
+}
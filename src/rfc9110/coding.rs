@@ -0,0 +1,595 @@
+//! I/O-free coroutine to decode a `gzip` or `deflate` coded body
+//! (RFC 9110 §8.4.1).
+//!
+//! The compressed bytes are pulled from an inner body reader, which
+//! depends on how the body is delimited: until the connection closes,
+//! by a `Content-Length`, or by chunked transfer coding. On each
+//! resume, only the bytes already received are decoded: the
+//! coroutine emits an I/O request as soon as more compressed input is
+//! needed, it never waits for the whole body.
+//!
+//! Requires the `compression` cargo feature.
+
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::{fmt, mem};
+
+use io_socket::{
+    coroutines::read::{SocketRead, SocketReadError, SocketReadResult},
+    io::{SocketInput, SocketOutput},
+};
+use miniz_oxide::{
+    DataFormat, MZError, MZFlush, MZStatus,
+    inflate::stream::{InflateState, inflate},
+};
+use thiserror::Error;
+
+use crate::rfc9112::chunk::{HttpChunksRead, HttpChunksReadError, HttpChunksReadResult};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_HEADER_LEN: usize = 10;
+const GZIP_TRAILER_LEN: usize = 8;
+const GZIP_CM_DEFLATE: u8 = 8;
+const GZIP_FHCRC: u8 = 1 << 1;
+const GZIP_FEXTRA: u8 = 1 << 2;
+const GZIP_FNAME: u8 = 1 << 3;
+const GZIP_FCOMMENT: u8 = 1 << 4;
+
+/// Size of the scratch buffer inflated data is written to.
+const OUTPUT_CHUNK_LEN: usize = 16 * 1024;
+
+/// Errors that can occur during the coroutine progression.
+#[derive(Debug, Error)]
+pub enum ContentDecodeError {
+    #[error("Received unexpected EOF in compressed body")]
+    UnexpectedEof,
+    #[error("Received invalid gzip header")]
+    InvalidGzipHeader,
+    #[error("Received gzip member with invalid checksum or size")]
+    InvalidGzipTrailer,
+    #[error("Inflate compressed body error: {0:?}")]
+    Inflate(MZError),
+    #[error("Decoded body exceeded {0} times its compressed size")]
+    RatioExceeded(usize),
+    #[error("Decoded body exceeded {0} bytes")]
+    DecodedLenExceeded(usize),
+    #[error(transparent)]
+    SocketRead(#[from] SocketReadError),
+    #[error(transparent)]
+    HttpChunksRead(#[from] HttpChunksReadError),
+}
+
+/// Result returned by [`ContentDecode::resume`].
+#[derive(Debug)]
+pub enum ContentDecodeResult {
+    /// The coroutine has successfully terminated its execution.
+    Ok { body: Vec<u8> },
+    /// The coroutine needs a socket I/O to be performed.
+    Io { input: SocketInput },
+    /// The coroutine encountered an error.
+    Err { err: ContentDecodeError },
+}
+
+/// Content coding supported by [`ContentDecode`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContentCoding {
+    /// The gzip file format (RFC 1952).
+    Gzip,
+    /// The zlib data format (RFC 1950).
+    ///
+    /// Raw deflate data (RFC 1951), wrongly sent by some servers, is
+    /// accepted as well.
+    Deflate,
+}
+
+impl ContentCoding {
+    /// Parses a content coding name, as found in `Content-Encoding`
+    /// or `Transfer-Encoding` headers (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+
+        if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else if name.eq_ignore_ascii_case("deflate") {
+            Some(Self::Deflate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Source of the compressed bytes.
+#[derive(Debug)]
+enum Source {
+    /// The body is delimited by the closure of the connection.
+    UntilClose(SocketRead),
+    /// The body length is known from the `Content-Length` header.
+    Lengthed { read: SocketRead, remaining: usize },
+    /// The body is decoded from chunked transfer coding.
+    Chunked(HttpChunksRead),
+}
+
+#[derive(Debug)]
+enum State {
+    /// Parse the gzip member header, or detect the deflate format.
+    Header,
+    /// Inflate the compressed data.
+    Data,
+    /// Verify the gzip member trailer.
+    Trailer,
+    /// A gzip member ended: another one may follow.
+    MemberEnd,
+    /// The compressed stream ended: drain the rest of the body.
+    Done,
+}
+
+/// I/O-free coroutine to decode a `gzip` or `deflate` coded body.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use io_http::rfc9110::coding::{ContentCoding, ContentDecode, ContentDecodeResult};
+/// use io_socket::{coroutines::read::SocketRead, runtimes::std_stream::handle};
+///
+/// let mut decode = ContentDecode::lengthed(ContentCoding::Gzip, SocketRead::default(), len);
+/// let mut arg = None;
+///
+/// let body = loop {
+///     match decode.resume(arg.take()) {
+///         ContentDecodeResult::Ok { body } => break body,
+///         ContentDecodeResult::Err { err } => panic!("{err}"),
+///         ContentDecodeResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+///     }
+/// };
+/// ```
+pub struct ContentDecode {
+    coding: ContentCoding,
+    source: Source,
+    state: State,
+    inflate: Box<InflateState>,
+    input: Vec<u8>,
+    eof: bool,
+    body: Vec<u8>,
+    member_start: usize,
+    compressed_len: usize,
+    max_ratio: Option<usize>,
+    max_decoded_len: Option<usize>,
+}
+
+impl ContentDecode {
+    /// Creates a new coroutine decoding a body delimited by the
+    /// closure of the connection.
+    pub fn new(coding: ContentCoding, read: SocketRead) -> Self {
+        Self::with_source(coding, Source::UntilClose(read))
+    }
+
+    /// Creates a new coroutine decoding a body of `len` compressed
+    /// bytes.
+    pub fn lengthed(coding: ContentCoding, read: SocketRead, len: usize) -> Self {
+        let source = Source::Lengthed {
+            read,
+            remaining: len,
+        };
+        Self::with_source(coding, source)
+    }
+
+    /// Creates a new coroutine decoding a body sent using chunked
+    /// transfer coding.
+    ///
+    /// The decoder is switched to streaming mode, so that chunks are
+    /// decompressed as soon as they are received.
+    pub fn chunked(coding: ContentCoding, read: HttpChunksRead) -> Self {
+        Self::with_source(coding, Source::Chunked(read.streaming(true)))
+    }
+
+    fn with_source(coding: ContentCoding, source: Source) -> Self {
+        Self {
+            coding,
+            source,
+            state: State::Header,
+            inflate: InflateState::new_boxed(DataFormat::Raw),
+            input: Vec::new(),
+            eof: false,
+            body: Vec::new(),
+            member_start: 0,
+            compressed_len: 0,
+            max_ratio: None,
+            max_decoded_len: None,
+        }
+    }
+
+    /// Sets the maximum ratio between the decoded body size and the
+    /// compressed bytes received so far (disabled by default).
+    ///
+    /// Guards against decompression bombs, where a tiny compressed
+    /// body expands to gigabytes: decoding stops with
+    /// [`ContentDecodeError::RatioExceeded`] as soon as the ratio is
+    /// exceeded.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Sets the maximum size of the decoded body (disabled by
+    /// default).
+    ///
+    /// Decoding stops with [`ContentDecodeError::DecodedLenExceeded`]
+    /// as soon as the decoded body grows past `len` bytes.
+    pub fn max_decoded_len(mut self, len: usize) -> Self {
+        self.max_decoded_len = Some(len);
+        self
+    }
+
+    /// Returns the trailer fields received after the last chunk, as
+    /// `(name, value)` pairs (names stored in lowercase).
+    ///
+    /// Always empty unless the body is sent using chunked transfer
+    /// coding. The list is only complete once the coroutine has
+    /// terminated.
+    pub fn trailers(&self) -> &[(String, String)] {
+        match &self.source {
+            Source::Chunked(read) => read.trailers(),
+            _ => &[],
+        }
+    }
+
+    /// Extends the inner buffer with compressed bytes already read.
+    ///
+    /// For a body of known length, bytes past the end of the body are
+    /// ignored.
+    pub fn extend(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        let start = self.input.len();
+        self.input.extend(bytes);
+
+        if let Source::Lengthed { remaining, .. } = &mut self.source {
+            let n = (self.input.len() - start).min(*remaining);
+            self.input.truncate(start + n);
+            *remaining -= n;
+        }
+
+        self.compressed_len += self.input.len() - start;
+    }
+
+    /// Advances the coroutine.
+    ///
+    /// Pass `None` on the first call. On subsequent calls, pass the
+    /// [`SocketOutput`] returned by the runtime after processing the
+    /// last emitted [`SocketInput`].
+    pub fn resume(&mut self, mut arg: Option<SocketOutput>) -> ContentDecodeResult {
+        loop {
+            let need_input = match self.state {
+                State::Header => match self.coding {
+                    ContentCoding::Gzip => match parse_gzip_header(&self.input) {
+                        Ok(Some(len)) => {
+                            self.input.drain(..len);
+                            self.inflate.reset(DataFormat::Raw);
+                            self.member_start = self.body.len();
+                            self.state = State::Data;
+                            false
+                        }
+                        Ok(None) => true,
+                        Err(err) => return ContentDecodeResult::Err { err },
+                    },
+                    ContentCoding::Deflate if self.input.len() < 2 => true,
+                    ContentCoding::Deflate => {
+                        let format = if is_zlib_header(&self.input) {
+                            DataFormat::Zlib
+                        } else {
+                            DataFormat::Raw
+                        };
+                        self.inflate.reset(format);
+                        self.state = State::Data;
+                        false
+                    }
+                },
+                State::Data => match self.inflate() {
+                    Ok(true) => {
+                        self.state = match self.coding {
+                            ContentCoding::Gzip => State::Trailer,
+                            ContentCoding::Deflate => State::Done,
+                        };
+                        false
+                    }
+                    Ok(false) => true,
+                    Err(err) => return ContentDecodeResult::Err { err },
+                },
+                State::Trailer if self.input.len() < GZIP_TRAILER_LEN => true,
+                State::Trailer => {
+                    let trailer: Vec<u8> = self.input.drain(..GZIP_TRAILER_LEN).collect();
+                    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+                    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+                    let member = &self.body[self.member_start..];
+
+                    if crc32(member) != crc || member.len() as u32 != size {
+                        return ContentDecodeResult::Err {
+                            err: ContentDecodeError::InvalidGzipTrailer,
+                        };
+                    }
+
+                    self.state = State::MemberEnd;
+                    false
+                }
+                // another gzip member follows (RFC 1952 §2.2)
+                State::MemberEnd if !self.input.is_empty() => {
+                    self.state = State::Header;
+                    false
+                }
+                State::MemberEnd | State::Done => {
+                    self.input.clear();
+
+                    if self.eof {
+                        return ContentDecodeResult::Ok {
+                            body: mem::take(&mut self.body),
+                        };
+                    }
+
+                    true
+                }
+            };
+
+            if !need_input {
+                continue;
+            }
+
+            if self.eof {
+                return ContentDecodeResult::Err {
+                    err: ContentDecodeError::UnexpectedEof,
+                };
+            }
+
+            if let Err(result) = self.read(arg.take()) {
+                return result;
+            }
+        }
+    }
+
+    /// Inflates the buffered compressed bytes into the body.
+    ///
+    /// Returns `true` when the end of the compressed stream has been
+    /// reached, `false` when more input is needed.
+    fn inflate(&mut self) -> Result<bool, ContentDecodeError> {
+        let mut output = vec![0; OUTPUT_CHUNK_LEN];
+
+        loop {
+            let result = inflate(&mut self.inflate, &self.input, &mut output, MZFlush::None);
+            self.input.drain(..result.bytes_consumed);
+            self.body.extend_from_slice(&output[..result.bytes_written]);
+            self.check_limits()?;
+
+            match result.status {
+                Ok(MZStatus::StreamEnd) => return Ok(true),
+                Ok(_) if result.bytes_consumed > 0 || result.bytes_written > 0 => continue,
+                // no progress can be made without more input
+                Ok(_) | Err(MZError::Buf) => return Ok(false),
+                Err(err) => return Err(ContentDecodeError::Inflate(err)),
+            }
+        }
+    }
+
+    /// Checks the decoded body against the configured limits.
+    fn check_limits(&self) -> Result<(), ContentDecodeError> {
+        if let Some(len) = self.max_decoded_len {
+            if self.body.len() > len {
+                return Err(ContentDecodeError::DecodedLenExceeded(len));
+            }
+        }
+
+        if let Some(ratio) = self.max_ratio {
+            if self.body.len() > self.compressed_len.saturating_mul(ratio) {
+                return Err(ContentDecodeError::RatioExceeded(ratio));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads more compressed bytes from the source into the inner
+    /// buffer.
+    ///
+    /// Returns the result to emit when the coroutine cannot go
+    /// further without I/O.
+    fn read(&mut self, arg: Option<SocketOutput>) -> Result<(), ContentDecodeResult> {
+        let (read, remaining) = match &mut self.source {
+            Source::UntilClose(read) => (read, None),
+            Source::Lengthed { remaining: 0, .. } => {
+                self.eof = true;
+                return Ok(());
+            }
+            Source::Lengthed { read, remaining } => (read, Some(remaining)),
+            Source::Chunked(read) => {
+                match read.resume(arg) {
+                    HttpChunksReadResult::Chunk { chunk } => {
+                        self.compressed_len += chunk.len();
+                        self.input.extend(chunk);
+                    }
+                    HttpChunksReadResult::Ok { .. } => self.eof = true,
+                    HttpChunksReadResult::Io { input } => {
+                        return Err(ContentDecodeResult::Io { input });
+                    }
+                    HttpChunksReadResult::Err { err } => {
+                        return Err(ContentDecodeResult::Err { err: err.into() });
+                    }
+                }
+
+                return Ok(());
+            }
+        };
+
+        match read.resume(arg) {
+            SocketReadResult::Ok { buf, n } => {
+                // never read past the end of a body of known length
+                let n = match remaining {
+                    Some(remaining) => {
+                        let n = n.min(*remaining);
+                        *remaining -= n;
+                        n
+                    }
+                    None => n,
+                };
+
+                self.compressed_len += n;
+                self.input.extend_from_slice(&buf[..n]);
+                read.replace(buf);
+            }
+            SocketReadResult::Eof => self.eof = true,
+            SocketReadResult::Io { input } => {
+                return Err(ContentDecodeResult::Io { input });
+            }
+            SocketReadResult::Err { err } => {
+                return Err(ContentDecodeResult::Err { err: err.into() });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ContentDecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentDecode")
+            .field("coding", &self.coding)
+            .field("source", &self.source)
+            .field("state", &self.state)
+            .field("input", &format_args!("[{} bytes]", self.input.len()))
+            .field("eof", &self.eof)
+            .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parses a gzip member header (RFC 1952 §2.3).
+///
+/// Returns the header length, or `None` if more bytes are needed.
+fn parse_gzip_header(buf: &[u8]) -> Result<Option<usize>, ContentDecodeError> {
+    if buf.len() < GZIP_HEADER_LEN {
+        return Ok(None);
+    }
+
+    if buf[..2] != GZIP_MAGIC || buf[2] != GZIP_CM_DEFLATE {
+        return Err(ContentDecodeError::InvalidGzipHeader);
+    }
+
+    let flags = buf[3];
+    let mut len = GZIP_HEADER_LEN;
+
+    if flags & GZIP_FEXTRA != 0 {
+        let Some(xlen) = buf.get(len..len + 2) else {
+            return Ok(None);
+        };
+        len += 2 + u16::from_le_bytes([xlen[0], xlen[1]]) as usize;
+    }
+
+    for flag in [GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            // zero-terminated string
+            let Some(end) = buf.get(len..).and_then(|b| memchr::memchr(0, b)) else {
+                return Ok(None);
+            };
+            len += end + 1;
+        }
+    }
+
+    if flags & GZIP_FHCRC != 0 {
+        len += 2;
+    }
+
+    if buf.len() < len {
+        return Ok(None);
+    }
+
+    Ok(Some(len))
+}
+
+/// Returns whether the given bytes start with a valid zlib header
+/// (RFC 1950 §2.2).
+fn is_zlib_header(buf: &[u8]) -> bool {
+    let (cmf, flg) = (buf[0], buf[1]);
+    cmf & 0x0f == GZIP_CM_DEFLATE && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0
+}
+
+/// CRC-32 lookup table (RFC 1952 §8).
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+
+        table[i] = c;
+        i += 1;
+    }
+
+    table
+};
+
+/// Computes the CRC-32 of the given bytes, as found in gzip member
+/// trailers.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for b in bytes {
+        crc = CRC32_TABLE[((crc ^ u32::from(*b)) & 0xff) as usize] ^ (crc >> 8);
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn coding_from_name() {
+        assert_eq!(ContentCoding::from_name("gzip"), Some(ContentCoding::Gzip));
+        assert_eq!(
+            ContentCoding::from_name(" X-GZIP"),
+            Some(ContentCoding::Gzip)
+        );
+        assert_eq!(
+            ContentCoding::from_name("Deflate"),
+            Some(ContentCoding::Deflate)
+        );
+        assert_eq!(ContentCoding::from_name("br"), None);
+    }
+
+    #[test]
+    fn gzip_header_minimal() {
+        let header = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+        assert_eq!(parse_gzip_header(&header).unwrap(), Some(10));
+        assert_eq!(parse_gzip_header(&header[..9]).unwrap(), None);
+    }
+
+    #[test]
+    fn gzip_header_with_name() {
+        let header = [0x1f, 0x8b, 8, GZIP_FNAME, 0, 0, 0, 0, 0, 0xff, b'a', 0];
+        assert_eq!(parse_gzip_header(&header).unwrap(), Some(12));
+        assert_eq!(parse_gzip_header(&header[..11]).unwrap(), None);
+    }
+
+    #[test]
+    fn gzip_header_invalid_magic() {
+        let header = [0x1f, 0x8c, 8, 0, 0, 0, 0, 0, 0, 0xff];
+        assert!(parse_gzip_header(&header).is_err());
+    }
+
+    #[test]
+    fn zlib_header_detection() {
+        assert!(is_zlib_header(&[0x78, 0x9c]));
+        assert!(is_zlib_header(&[0x78, 0x01]));
+        assert!(!is_zlib_header(&[0xcb, 0x48]));
+    }
+}
@@ -0,0 +1,64 @@
+//! Conditional request helpers (RFC 9110 §13).
+//!
+//! These helpers build the values of the precondition header fields,
+//! so that a cached representation can be revalidated:
+//!
+//! ```rust,ignore
+//! use io_http::rfc9110::{conditional, headers::IF_NONE_MATCH, request::HttpRequest};
+//!
+//! let request = HttpRequest::get(url).header(IF_NONE_MATCH, conditional::if_none_match("abc"));
+//! ```
+//!
+//! When the representation did not change, the server responds with
+//! `304 Not Modified`, reported by
+//! [`crate::rfc9112::send::Http11SendResult::Ok`] as `not_modified`.
+
+use alloc::{format, string::String};
+
+use crate::rfc9110::date::format_http_date;
+
+/// Builds an `If-None-Match` value from the given entity tag.
+///
+/// The tag is quoted unless it already is, either as a strong
+/// (`"abc"`) or a weak (`W/"abc"`) entity tag. The wildcard `*` is
+/// kept as is.
+pub fn if_none_match(etag: impl AsRef<str>) -> String {
+    let etag = etag.as_ref();
+
+    if etag == "*" || etag.starts_with('"') || etag.starts_with("W/\"") {
+        etag.into()
+    } else {
+        format!("\"{etag}\"")
+    }
+}
+
+/// Builds an `If-Modified-Since` value from the given UNIX timestamp
+/// (in seconds), formatted as an IMF-fixdate.
+pub fn if_modified_since(timestamp: u64) -> String {
+    format_http_date(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_none_match_quotes_bare_tag() {
+        assert_eq!(if_none_match("abc"), "\"abc\"");
+    }
+
+    #[test]
+    fn if_none_match_keeps_quoted_tags() {
+        assert_eq!(if_none_match("\"abc\""), "\"abc\"");
+        assert_eq!(if_none_match("W/\"abc\""), "W/\"abc\"");
+        assert_eq!(if_none_match("*"), "*");
+    }
+
+    #[test]
+    fn if_modified_since_uses_imf_fixdate() {
+        assert_eq!(
+            if_modified_since(784111777),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+}
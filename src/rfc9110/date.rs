@@ -0,0 +1,148 @@
+//! HTTP date format (RFC 9110 §5.6.7).
+//!
+//! HTTP dates are always sent using the IMF-fixdate format, a fixed
+//! length subset of the RFC 5322 date format, in GMT:
+//!
+//! ```text
+//! Sun, 06 Nov 1994 08:49:37 GMT
+//! ```
+
+use alloc::{format, string::String};
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Formats the given UNIX timestamp (in seconds) as an IMF-fixdate.
+pub fn format_http_date(timestamp: u64) -> String {
+    let days = timestamp / 86400;
+    let secs = timestamp % 86400;
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        DAYS[(days % 7) as usize],
+        MONTHS[month as usize - 1],
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    )
+}
+
+/// Parses the given IMF-fixdate into a UNIX timestamp (in seconds).
+///
+/// Returns `None` if the date is malformed or before the UNIX epoch.
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    let (_, date) = date.trim().split_once(", ")?;
+    let mut parts = date.split(' ');
+
+    let day: u64 = parse_digits(parts.next()?, 2)?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year: u64 = parse_digits(parts.next()?, 4)?;
+
+    let mut time = parts.next()?.split(':');
+    let hour: u64 = parse_digits(time.next()?, 2)?;
+    let min: u64 = parse_digits(time.next()?, 2)?;
+    let sec: u64 = parse_digits(time.next()?, 2)?;
+
+    if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some() {
+        return None;
+    }
+
+    if !(1..=31).contains(&day) || year < 1970 || hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    Some(days * 86400 + hour * 3600 + min * 60 + sec)
+}
+
+/// Parses exactly `len` ASCII digits.
+fn parse_digits(s: &str, len: usize) -> Option<u64> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    s.parse().ok()
+}
+
+/// Converts a `(year, month, day)` civil date of the proleptic
+/// Gregorian calendar into a number of days since the UNIX epoch.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year % 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Converts a number of days since the UNIX epoch into a `(year,
+/// month, day)` civil date of the proleptic Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_epoch() {
+        assert_eq!(format_http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[test]
+    fn format_rfc_example() {
+        assert_eq!(format_http_date(784111777), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn parse_rfc_example() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+    }
+
+    #[test]
+    fn parse_format_roundtrip() {
+        for timestamp in [0, 951825600, 1700000000, 4102444800] {
+            assert_eq!(
+                parse_http_date(&format_http_date(timestamp)),
+                Some(timestamp)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse_http_date(""), None);
+        assert_eq!(parse_http_date("Sun, 6 Nov 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn format_leap_day() {
+        assert_eq!(format_http_date(951825600), "Tue, 29 Feb 2000 12:00:00 GMT");
+    }
+}
@@ -12,13 +12,123 @@ pub const SENSITIVE_HEADERS: &[&str] = &[
     PROXY_AUTHENTICATE,
 ];
 
+/// Header names that must not be sent as trailer fields, as they are
+/// needed before the content is processed (RFC 9110 §6.5.1).
+pub const FORBIDDEN_TRAILERS: &[&str] = &[
+    AUTHORIZATION,
+    CACHE_CONTROL,
+    CONTENT_ENCODING,
+    CONTENT_LENGTH,
+    CONTENT_RANGE,
+    CONTENT_TYPE,
+    COOKIE,
+    EXPECT,
+    HOST,
+    MAX_FORWARDS,
+    PRAGMA,
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    RANGE,
+    SET_COOKIE,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    WWW_AUTHENTICATE,
+];
+
+/// Header names that only apply to the current connection, and must
+/// not be forwarded by intermediaries (RFC 9110 §7.6.1).
+pub const HOP_BY_HOP_HEADERS: &[&str] = &[
+    CONNECTION,
+    KEEP_ALIVE,
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    UPGRADE,
+];
+
+pub const ACCEPT_RANGES: &str = "accept-ranges";
 pub const AUTHORIZATION: &str = "authorization";
+pub const CACHE_CONTROL: &str = "cache-control";
 pub const CONNECTION: &str = "connection";
+pub const CONTENT_ENCODING: &str = "content-encoding";
 pub const CONTENT_LENGTH: &str = "content-length";
+pub const CONTENT_RANGE: &str = "content-range";
+pub const CONTENT_TYPE: &str = "content-type";
 pub const COOKIE: &str = "cookie";
+pub const ETAG: &str = "etag";
+pub const EXPECT: &str = "expect";
+pub const HOST: &str = "host";
+pub const IF_MODIFIED_SINCE: &str = "if-modified-since";
+pub const IF_NONE_MATCH: &str = "if-none-match";
+pub const IF_RANGE: &str = "if-range";
+pub const KEEP_ALIVE: &str = "keep-alive";
+pub const LAST_MODIFIED: &str = "last-modified";
 pub const LOCATION: &str = "location";
+pub const MAX_FORWARDS: &str = "max-forwards";
+pub const PRAGMA: &str = "pragma";
 pub const PROXY_AUTHENTICATE: &str = "proxy-authenticate";
 pub const PROXY_AUTHORIZATION: &str = "proxy-authorization";
+pub const RANGE: &str = "range";
+pub const RETRY_AFTER: &str = "retry-after";
 pub const SET_COOKIE: &str = "set-cookie";
+pub const TE: &str = "te";
+pub const TRAILER: &str = "trailer";
 pub const TRANSFER_ENCODING: &str = "transfer-encoding";
+pub const UPGRADE: &str = "upgrade";
 pub const WWW_AUTHENTICATE: &str = "www-authenticate";
+
+/// Returns whether the given comma-separated header value contains
+/// the given token, compared case-insensitively (RFC 9110 §5.6.1).
+///
+/// Used for list-based fields such as `Connection: keep-alive, Upgrade`.
+pub fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+/// Returns whether the given string is a valid token (RFC 9110
+/// §5.6.2), as required for field names and methods.
+pub fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_token_valid() {
+        assert!(is_token("GET"));
+        assert!(is_token("PROPFIND"));
+        assert!(is_token("x-custom_method.v2"));
+    }
+
+    #[test]
+    fn is_token_invalid() {
+        assert!(!is_token(""));
+        assert!(!is_token("GET /"));
+        assert!(!is_token("GET\r\n"));
+        assert!(!is_token("M(ETHOD)"));
+    }
+
+    #[test]
+    fn has_token_single() {
+        assert!(has_token("close", "close"));
+        assert!(has_token("Keep-Alive", "keep-alive"));
+        assert!(!has_token("close", "keep-alive"));
+    }
+
+    #[test]
+    fn has_token_list() {
+        assert!(has_token("Upgrade, Keep-Alive", "keep-alive"));
+        assert!(has_token("upgrade,close", "close"));
+        assert!(!has_token("keep-alive-ish, upgrade", "keep-alive"));
+    }
+}
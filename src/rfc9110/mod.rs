@@ -5,7 +5,15 @@
 //! and the abstract request/response message structure that HTTP/1.0,
 //! HTTP/1.1, HTTP/2, and HTTP/3 all implement.
 
+pub mod auth;
+#[cfg(feature = "compression")]
+pub mod coding;
+pub mod conditional;
+pub mod date;
 pub mod headers;
+pub mod range;
+pub mod redirect;
 pub mod request;
 pub mod response;
 pub mod status;
+pub mod uri;
@@ -0,0 +1,231 @@
+//! Range request helpers (RFC 9110 §14).
+//!
+//! These helpers resume an interrupted download, by requesting the
+//! bytes following the ones already received:
+//!
+//! ```rust,ignore
+//! use io_http::rfc9110::range;
+//!
+//! // `request` was sent, `response` holds the first bytes only
+//! let request = range::resume_request(&request, &response)?;
+//! ```
+//!
+//! The server answers with `206 Partial Content`, whose body must be
+//! appended to the bytes already received.
+
+use alloc::{format, string::String};
+
+use thiserror::Error;
+
+use crate::rfc9110::{
+    headers::{ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, RANGE, has_token},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+/// Errors that can occur while building a range request.
+#[derive(Debug, Error)]
+pub enum RangeError {
+    #[error("Server does not accept byte range requests")]
+    RangesNotSupported,
+    #[error("Received invalid Content-Range {0}")]
+    InvalidContentRange(String),
+}
+
+/// Builds a `Range` value requesting all the bytes from the given
+/// offset (e.g. `bytes=500-`).
+pub fn range_from(offset: u64) -> String {
+    format!("bytes={offset}-")
+}
+
+/// Parses the first and last byte positions of a `Content-Range`
+/// value, such as `bytes 0-499/1234`.
+///
+/// Returns `None` for unsatisfied ranges (`bytes */1234`) and invalid
+/// values.
+pub fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let (unit, rest) = value.trim().split_once(' ')?;
+
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let (range, _) = rest.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let first = first.trim().parse().ok()?;
+    let last = last.trim().parse().ok()?;
+
+    (first <= last).then_some((first, last))
+}
+
+/// Builds the request resuming the download of `response`, sent for
+/// `request`.
+///
+/// The new request asks for the bytes following the ones received in
+/// `response`, as a `206 Partial Content` response when it carries a
+/// `Content-Range`, otherwise from the start of the representation.
+/// When the response has a strong `ETag`, an `If-Range` precondition
+/// is added, so that a changed representation is sent in full instead
+/// of being spliced.
+///
+/// # Errors
+///
+/// Returns [`RangeError::RangesNotSupported`] if the response does not
+/// advertise `Accept-Ranges: bytes`, and
+/// [`RangeError::InvalidContentRange`] if its `Content-Range` cannot
+/// be parsed, or if the received bytes would end past the largest
+/// representable offset.
+pub fn resume_request(
+    request: &HttpRequest,
+    response: &HttpResponse,
+) -> Result<HttpRequest, RangeError> {
+    let accept_ranges = response.header(ACCEPT_RANGES).unwrap_or_default();
+
+    if !has_token(accept_ranges, "bytes") {
+        return Err(RangeError::RangesNotSupported);
+    }
+
+    let len = response.body.len() as u64;
+
+    let offset = match response.header(CONTENT_RANGE) {
+        Some(value) if *response.status == 206 => parse_content_range(value)
+            .and_then(|(first, _)| first.checked_add(len))
+            .ok_or_else(|| RangeError::InvalidContentRange(value.into()))?,
+        _ => len,
+    };
+
+    let mut request = request.clone();
+    request.headers.retain(|(name, _)| {
+        !name.eq_ignore_ascii_case(RANGE) && !name.eq_ignore_ascii_case(IF_RANGE)
+    });
+
+    let mut request = request.header(RANGE, range_from(offset));
+
+    if let Some(etag) = response.header(ETAG) {
+        if !etag.starts_with("W/") {
+            request = request.header(IF_RANGE, etag);
+        }
+    }
+
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use url::Url;
+
+    use crate::rfc9110::status::StatusCode;
+
+    use super::*;
+
+    fn request() -> HttpRequest {
+        let url = Url::parse("http://example.com/file").unwrap();
+        HttpRequest::get(url).header("Host", "example.com")
+    }
+
+    fn response(status: u16, headers: &[(&str, &str)], len: usize) -> HttpResponse {
+        HttpResponse {
+            status: StatusCode(status),
+            reason: String::new(),
+            version: String::new(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| ((*k).into(), (*v).into()))
+                .collect(),
+            body: vec![0; len],
+            trailers: vec![],
+        }
+    }
+
+    fn headers(request: &HttpRequest, name: &str) -> Vec<String> {
+        request
+            .headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    #[test]
+    fn resume_from_byte_500() {
+        let response = response(200, &[("accept-ranges", "bytes")], 500);
+        let request = resume_request(&request(), &response).unwrap();
+        assert_eq!(headers(&request, RANGE), ["bytes=500-"]);
+        assert_eq!(headers(&request, "host"), ["example.com"]);
+    }
+
+    #[test]
+    fn resume_after_partial_content() {
+        let response = response(
+            206,
+            &[
+                ("accept-ranges", "bytes"),
+                ("content-range", "bytes 500-999/2000"),
+            ],
+            300,
+        );
+
+        let request = request().header("Range", "bytes=500-");
+        let request = resume_request(&request, &response).unwrap();
+        assert_eq!(headers(&request, RANGE), ["bytes=800-"]);
+    }
+
+    #[test]
+    fn resume_with_strong_etag_only() {
+        let strong = response(200, &[("accept-ranges", "bytes"), ("etag", "\"v1\"")], 10);
+        let next = resume_request(&request(), &strong).unwrap();
+        assert_eq!(headers(&next, IF_RANGE), ["\"v1\""]);
+
+        let weak = response(200, &[("accept-ranges", "bytes"), ("etag", "W/\"v1\"")], 10);
+        let next = resume_request(&request(), &weak).unwrap();
+        assert!(headers(&next, IF_RANGE).is_empty());
+    }
+
+    #[test]
+    fn err_on_ranges_not_supported() {
+        let none = response(200, &[], 500);
+        let result = resume_request(&request(), &none);
+        assert!(matches!(result, Err(RangeError::RangesNotSupported)));
+
+        let refused = response(200, &[("accept-ranges", "none")], 500);
+        let result = resume_request(&request(), &refused);
+        assert!(matches!(result, Err(RangeError::RangesNotSupported)));
+    }
+
+    #[test]
+    fn err_on_invalid_content_range() {
+        let headers = [("accept-ranges", "bytes"), ("content-range", "bytes 500")];
+        let result = resume_request(&request(), &response(206, &headers, 10));
+        assert!(matches!(result, Err(RangeError::InvalidContentRange(_))));
+    }
+
+    #[test]
+    fn err_on_content_range_overflow() {
+        let range = "bytes 18446744073709551615-18446744073709551615/*";
+        let headers = [("accept-ranges", "bytes"), ("content-range", range)];
+        let result = resume_request(&request(), &response(206, &headers, 1));
+        assert!(matches!(result, Err(RangeError::InvalidContentRange(value)) if value == range));
+    }
+
+    #[test]
+    fn range_from_offset() {
+        assert_eq!(range_from(0), "bytes=0-");
+        assert_eq!(range_from(500), "bytes=500-");
+    }
+
+    #[test]
+    fn content_range_valid() {
+        assert_eq!(parse_content_range("bytes 0-499/1234"), Some((0, 499)));
+        assert_eq!(parse_content_range("bytes 500-1233/*"), Some((500, 1233)));
+    }
+
+    #[test]
+    fn content_range_invalid() {
+        assert_eq!(parse_content_range("bytes */1234"), None);
+        assert_eq!(parse_content_range("bytes 10-5/1234"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes 0-499"), None);
+    }
+}
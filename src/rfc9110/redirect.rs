@@ -0,0 +1,56 @@
+//! Redirect policy (RFC 9110 §15.4).
+//!
+//! Send coroutines never follow redirects on their own: they surface
+//! them to the caller. A [`RedirectPolicy`] lets the caller decide,
+//! before each hop, whether a redirect should be surfaced, returned
+//! as a plain response, or rejected.
+
+use alloc::sync::Arc;
+use core::fmt;
+
+use url::Url;
+
+use crate::rfc9110::{request::HttpRequest, response::HttpResponse};
+
+/// Action to take on a redirect response.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RedirectAction {
+    /// Surface the redirect so the caller can follow it.
+    Follow,
+    /// Stop following redirects, and return the 3xx response as-is.
+    Stop,
+    /// Reject the redirect with an error.
+    Error,
+}
+
+type RedirectFn = dyn Fn(&HttpRequest, &HttpResponse, &Url) -> RedirectAction + Send + Sync;
+
+/// User-supplied redirect predicate, invoked with the request that
+/// was sent, the 3xx response received and the resolved target URL.
+#[derive(Clone)]
+pub struct RedirectPolicy(Arc<RedirectFn>);
+
+impl RedirectPolicy {
+    /// Creates a new redirect policy from the given predicate.
+    pub fn new(
+        f: impl Fn(&HttpRequest, &HttpResponse, &Url) -> RedirectAction + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Returns the action to take on the given redirect.
+    pub fn action(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        url: &Url,
+    ) -> RedirectAction {
+        (self.0)(request, response, url)
+    }
+}
+
+impl fmt::Debug for RedirectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RedirectPolicy(..)")
+    }
+}
@@ -21,6 +21,11 @@ pub struct HttpRequest {
     pub headers: Vec<(String, String)>,
     /// Request body bytes.
     pub body: Vec<u8>,
+    /// Trailer fields sent after the body, as `(name, value)` pairs.
+    ///
+    /// Trailers require chunked transfer coding, so they are only
+    /// sent by HTTP/1.1 coroutines.
+    pub trailers: Vec<(String, String)>,
 }
 
 impl HttpRequest {
@@ -31,6 +36,7 @@ impl HttpRequest {
             url,
             headers: Vec::new(),
             body: Vec::new(),
+            trailers: Vec::new(),
         }
     }
 
@@ -45,25 +51,39 @@ impl HttpRequest {
         self.body = body;
         self
     }
+
+    /// Appends a trailer field.
+    ///
+    /// Trailers are sent using chunked transfer coding, hence with
+    /// HTTP/1.1 only: [`Http10Send`](crate::rfc1945::send::Http10Send)
+    /// fails with
+    /// [`Http10SendError::TrailersNotSupported`](crate::rfc1945::send::Http10SendError::TrailersNotSupported).
+    pub fn trailer(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.trailers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Redacts the values of [`SENSITIVE_HEADERS`] for debug output.
+fn redact(headers: &[(String, String)]) -> Vec<(&str, &str)> {
+    headers
+        .iter()
+        .map(|(k, v)| {
+            let sensitive = SENSITIVE_HEADERS.iter().any(|s| k.eq_ignore_ascii_case(s));
+            let v = if sensitive { "[REDACTED]" } else { v.as_str() };
+            (k.as_str(), v)
+        })
+        .collect()
 }
 
 impl fmt::Debug for HttpRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let headers: Vec<(&str, &str)> = self
-            .headers
-            .iter()
-            .map(|(k, v)| {
-                let sensitive = SENSITIVE_HEADERS.iter().any(|s| k.eq_ignore_ascii_case(s));
-                let v = if sensitive { "[REDACTED]" } else { v.as_str() };
-                (k.as_str(), v)
-            })
-            .collect();
-
         f.debug_struct("HttpRequest")
             .field("method", &self.method)
             .field("url", &self.url.as_str())
-            .field("headers", &headers)
+            .field("headers", &redact(&self.headers))
             .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .field("trailers", &redact(&self.trailers))
             .finish()
     }
 }
@@ -102,6 +122,14 @@ mod tests {
         assert_eq!(req.body, b"hello");
     }
 
+    #[test]
+    fn trailer_appended() {
+        let url = Url::parse("http://example.com/").unwrap();
+        let req = HttpRequest::get(url).trailer("X-Checksum", "abc");
+        assert!(req.headers.is_empty());
+        assert_eq!(req.trailers, [("X-Checksum".into(), "abc".into())]);
+    }
+
     #[test]
     fn debug_redacts_sensitive_headers() {
         let url = Url::parse("http://example.com/").unwrap();
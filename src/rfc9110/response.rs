@@ -1,21 +1,34 @@
 //! HTTP response type (RFC 9110 §15).
 
 use alloc::{string::String, vec::Vec};
-use core::fmt;
+use core::{fmt, time::Duration};
 
-use crate::rfc9110::{headers::SENSITIVE_HEADERS, status::StatusCode};
+use thiserror::Error;
+use url::Url;
+
+use crate::rfc9110::{
+    date::parse_http_date,
+    headers::{LOCATION, RETRY_AFTER, SENSITIVE_HEADERS},
+    status::StatusCode,
+};
 
 /// An incoming HTTP response.
 #[derive(Clone)]
 pub struct HttpResponse {
     /// HTTP status code.
     pub status: StatusCode,
+    /// Reason phrase sent along with the status code (e.g. `"Not
+    /// Found"`), possibly empty.
+    pub reason: String,
     /// HTTP protocol version string (e.g. `"HTTP/1.1"`, `"HTTP/1.0"`).
     pub version: String,
     /// Response headers as `(name, value)` pairs (names stored in lowercase).
     pub headers: Vec<(String, String)>,
     /// Response body bytes.
     pub body: Vec<u8>,
+    /// Trailer fields received after a chunked body, as `(name,
+    /// value)` pairs (names stored in lowercase).
+    pub trailers: Vec<(String, String)>,
 }
 
 impl HttpResponse {
@@ -27,6 +40,70 @@ impl HttpResponse {
             .find(|(k, _)| k.eq_ignore_ascii_case(name))
             .map(|(_, v)| v.as_str())
     }
+
+    /// Resolves the `Location` header against the given request URL
+    /// (RFC 9110 §10.2.2), if any.
+    ///
+    /// Relative references are resolved following RFC 3986 §5.2, and
+    /// the request URL fragment is inherited when the `Location` does
+    /// not carry one.
+    pub fn location(&self, base: &Url) -> Option<Url> {
+        let mut url = base.join(self.header(LOCATION)?).ok()?;
+
+        if url.fragment().is_none() {
+            url.set_fragment(base.fragment());
+        }
+
+        Some(url)
+    }
+
+    /// Returns the delay to wait before retrying, as sent by the
+    /// server in the `Retry-After` header (RFC 9110 §10.2.3).
+    ///
+    /// The header is either a number of seconds, or an HTTP date. As
+    /// the crate does not access the clock, the current UNIX
+    /// timestamp (in seconds) is given as `now` to compute the delay
+    /// from a date. A date in the past gives a zero delay.
+    pub fn retry_after(&self, now: u64) -> Option<Duration> {
+        let value = self.header(RETRY_AFTER)?.trim();
+
+        let secs = if value.bytes().all(|b| b.is_ascii_digit()) {
+            value.parse().ok()?
+        } else {
+            parse_http_date(value)?.saturating_sub(now)
+        };
+
+        Some(Duration::from_secs(secs))
+    }
+
+    /// Turns a `4xx` or `5xx` response into an [`HttpStatusError`],
+    /// passing other responses through.
+    ///
+    /// The error keeps the response body, so that API error payloads
+    /// can still be inspected.
+    pub fn error_for_status(self) -> Result<Self, HttpStatusError> {
+        if self.status.is_client_error() || self.status.is_server_error() {
+            Err(HttpStatusError {
+                status: self.status,
+                reason: self.reason,
+                body: self.body,
+            })
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Error returned by [`HttpResponse::error_for_status`].
+#[derive(Clone, Debug, Error)]
+#[error("Received HTTP error status {} {reason}", status.0)]
+pub struct HttpStatusError {
+    /// HTTP status code.
+    pub status: StatusCode,
+    /// Reason phrase sent along with the status code, possibly empty.
+    pub reason: String,
+    /// Response body bytes.
+    pub body: Vec<u8>,
 }
 
 /// Incremental builder for [`HttpResponse`], used internally by
@@ -34,6 +111,7 @@ impl HttpResponse {
 #[derive(Clone, Debug)]
 pub(crate) struct ResponseBuilder {
     pub(crate) status: Option<StatusCode>,
+    pub(crate) reason: String,
     pub(crate) version: String,
     pub(crate) headers: Vec<(String, String)>,
 }
@@ -42,6 +120,7 @@ impl Default for ResponseBuilder {
     fn default() -> Self {
         Self {
             status: None,
+            reason: String::new(),
             version: "HTTP/1.1".into(),
             headers: Vec::new(),
         }
@@ -64,13 +143,24 @@ impl ResponseBuilder {
             .map(|(_, v)| v.as_str())
     }
 
+    /// Returns the values of all headers with the given name
+    /// (case-insensitive), in order.
+    pub(crate) fn get_headers<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
     /// Finalizes the builder into an [`HttpResponse`].
     pub(crate) fn build(self, body: Vec<u8>) -> HttpResponse {
         HttpResponse {
             status: self.status.unwrap_or(StatusCode(200)),
+            reason: self.reason,
             version: self.version,
             headers: self.headers,
             body,
+            trailers: Vec::new(),
         }
     }
 }
@@ -89,9 +179,11 @@ impl fmt::Debug for HttpResponse {
 
         f.debug_struct("HttpRequest")
             .field("status", &self.status)
+            .field("reason", &self.reason)
             .field("version", &self.version)
             .field("headers", &headers)
             .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .field("trailers", &self.trailers)
             .finish()
     }
 }
@@ -102,13 +194,78 @@ mod tests {
 
     use super::*;
 
+    fn location(base: &str, location: &str) -> Option<String> {
+        let base = Url::parse(base).unwrap();
+        let response = HttpResponse {
+            status: StatusCode(302),
+            reason: String::new(),
+            version: String::new(),
+            headers: vec![("location".into(), location.into())],
+            body: vec![],
+            trailers: vec![],
+        };
+        response.location(&base).map(String::from)
+    }
+
+    #[test]
+    fn location_query_only() {
+        let url = location("http://example.com/a/b?page=1", "?page=2");
+        assert_eq!(url.as_deref(), Some("http://example.com/a/b?page=2"));
+    }
+
+    #[test]
+    fn location_fragment_only() {
+        let url = location("http://example.com/a/b?page=1", "#top");
+        assert_eq!(url.as_deref(), Some("http://example.com/a/b?page=1#top"));
+    }
+
+    #[test]
+    fn location_scheme_relative() {
+        let url = location("https://example.com/a/b", "//other.com/path");
+        assert_eq!(url.as_deref(), Some("https://other.com/path"));
+    }
+
+    #[test]
+    fn location_dot_segments() {
+        let url = location("http://example.com/a/b/c", "../d");
+        assert_eq!(url.as_deref(), Some("http://example.com/a/d"));
+    }
+
+    #[test]
+    fn location_inherits_fragment() {
+        let url = location("http://example.com/a#section", "/b");
+        assert_eq!(url.as_deref(), Some("http://example.com/b#section"));
+    }
+
+    #[test]
+    fn location_keeps_own_fragment() {
+        let url = location("http://example.com/a#section", "/b#other");
+        assert_eq!(url.as_deref(), Some("http://example.com/b#other"));
+    }
+
+    #[test]
+    fn location_missing_returns_none() {
+        let base = Url::parse("http://example.com/").unwrap();
+        let response = HttpResponse {
+            status: StatusCode(302),
+            reason: String::new(),
+            version: String::new(),
+            headers: vec![],
+            body: vec![],
+            trailers: vec![],
+        };
+        assert_eq!(response.location(&base), None);
+    }
+
     #[test]
     fn header_case_insensitive() {
         let response = HttpResponse {
             status: StatusCode(200),
+            reason: String::new(),
             version: String::new(),
             headers: vec![("Content-Type".into(), "text/html".into())],
             body: vec![],
+            trailers: vec![],
         };
         assert_eq!(response.header("content-type"), Some("text/html"));
         assert_eq!(response.header("CONTENT-TYPE"), Some("text/html"));
@@ -119,9 +276,11 @@ mod tests {
     fn header_missing_returns_none() {
         let response = HttpResponse {
             status: StatusCode(200),
+            reason: String::new(),
             version: String::new(),
             headers: vec![],
             body: vec![],
+            trailers: vec![],
         };
         assert_eq!(response.header("x-missing"), None);
     }
@@ -130,12 +289,14 @@ mod tests {
     fn header_returns_first_match() {
         let response = HttpResponse {
             status: StatusCode(200),
+            reason: String::new(),
             version: String::new(),
             headers: vec![
                 ("X-Foo".into(), "first".into()),
                 ("x-foo".into(), "second".into()),
             ],
             body: vec![],
+            trailers: vec![],
         };
         assert_eq!(response.header("x-foo"), Some("first"));
     }
@@ -156,6 +317,81 @@ mod tests {
         assert_eq!(builder.get_header("CONTENT-TYPE"), Some("text/html"));
     }
 
+    fn response(status: u16, reason: &str) -> HttpResponse {
+        HttpResponse {
+            status: StatusCode(status),
+            reason: reason.into(),
+            version: String::new(),
+            headers: vec![],
+            body: b"payload".to_vec(),
+            trailers: vec![],
+        }
+    }
+
+    fn retry_after(value: &str, now: u64) -> Option<Duration> {
+        let mut response = response(429, "Too Many Requests");
+        response.headers.push(("retry-after".into(), value.into()));
+        response.retry_after(now)
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(retry_after("120", 0), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn retry_after_date() {
+        let now = 784111777 - 90;
+        let delay = retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now);
+        assert_eq!(delay, Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn retry_after_past_date() {
+        let delay = retry_after("Sun, 06 Nov 1994 08:49:37 GMT", 784111777 + 10);
+        assert_eq!(delay, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_invalid_or_missing() {
+        assert_eq!(retry_after("soon", 0), None);
+        assert_eq!(response(503, "").retry_after(0), None);
+    }
+
+    #[test]
+    fn error_for_status_passes_success_through() {
+        let response = response(200, "OK").error_for_status().unwrap();
+        assert_eq!(*response.status, 200);
+        assert_eq!(response.body, b"payload");
+    }
+
+    #[test]
+    fn error_for_status_on_client_error() {
+        let err = response(404, "Not Found").error_for_status().unwrap_err();
+        assert_eq!(*err.status, 404);
+        assert_eq!(err.reason, "Not Found");
+        assert_eq!(err.body, b"payload");
+    }
+
+    #[test]
+    fn error_for_status_on_server_error() {
+        let err = response(500, "Internal Server Error")
+            .error_for_status()
+            .unwrap_err();
+        assert_eq!(*err.status, 500);
+        assert_eq!(err.body, b"payload");
+    }
+
+    #[test]
+    fn builder_get_headers_returns_all_matches() {
+        let mut builder = ResponseBuilder::default();
+        builder.header("Set-Cookie", b"a=1");
+        builder.header("Content-Type", b"text/html");
+        builder.header("set-cookie", b"b=2");
+        let values: Vec<&str> = builder.get_headers("set-cookie").collect();
+        assert_eq!(values, ["a=1", "b=2"]);
+    }
+
     #[test]
     fn builder_build_defaults_to_200() {
         let response = ResponseBuilder::default().build(vec![]);
@@ -16,6 +16,16 @@ impl StatusCode {
     pub fn is_redirection(self) -> bool {
         self.0 >= 300 && self.0 < 400
     }
+
+    /// Returns `true` if the status code is in the `4xx` range.
+    pub fn is_client_error(self) -> bool {
+        self.0 >= 400 && self.0 < 500
+    }
+
+    /// Returns `true` if the status code is in the `5xx` range.
+    pub fn is_server_error(self) -> bool {
+        self.0 >= 500 && self.0 < 600
+    }
 }
 
 impl Deref for StatusCode {
@@ -47,4 +57,20 @@ mod tests {
         assert!(!StatusCode(299).is_redirection());
         assert!(!StatusCode(400).is_redirection());
     }
+
+    #[test]
+    fn client_error_range() {
+        assert!(StatusCode(400).is_client_error());
+        assert!(StatusCode(499).is_client_error());
+        assert!(!StatusCode(399).is_client_error());
+        assert!(!StatusCode(500).is_client_error());
+    }
+
+    #[test]
+    fn server_error_range() {
+        assert!(StatusCode(500).is_server_error());
+        assert!(StatusCode(599).is_server_error());
+        assert!(!StatusCode(499).is_server_error());
+        assert!(!StatusCode(600).is_server_error());
+    }
 }
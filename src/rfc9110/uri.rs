@@ -0,0 +1,50 @@
+//! HTTP-related URI schemes (RFC 9110 §4.2).
+//!
+//! The `http` and `https` schemes come with a default port, used
+//! when the authority of a URI omits it.
+
+/// Returns the default port of the given URI scheme, compared
+/// case-insensitively: 80 for `http`, 443 for `https`, `None` for
+/// any other scheme.
+///
+/// # Example
+///
+/// ```rust
+/// use io_http::rfc9110::uri::default_port;
+///
+/// assert_eq!(default_port("https"), Some(443));
+/// assert_eq!(default_port("ftp"), None);
+/// ```
+pub fn default_port(scheme: &str) -> Option<u16> {
+    if scheme.eq_ignore_ascii_case("http") {
+        Some(80)
+    } else if scheme.eq_ignore_ascii_case("https") {
+        Some(443)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_default_port() {
+        assert_eq!(default_port("http"), Some(80));
+        assert_eq!(default_port("HTTP"), Some(80));
+    }
+
+    #[test]
+    fn https_default_port() {
+        assert_eq!(default_port("https"), Some(443));
+        assert_eq!(default_port("Https"), Some(443));
+    }
+
+    #[test]
+    fn unknown_scheme_without_default_port() {
+        assert_eq!(default_port("ws"), None);
+        assert_eq!(default_port("ftp"), None);
+        assert_eq!(default_port(""), None);
+    }
+}
@@ -18,6 +18,11 @@
 //! [`super::send::Http11Send`] when the response carries
 //! `Transfer-Encoding: chunked`. It can also be used standalone when
 //! only the body stream is available.
+//!
+//! By default the decoded body is buffered and returned at once. When
+//! [`HttpChunksRead::streaming`] is enabled, each decoded chunk is
+//! emitted as soon as it is complete, which suits long-lived streams
+//! such as server-sent events.
 
 use alloc::{
     string::{String, ToString},
@@ -47,6 +52,10 @@ pub enum HttpChunksReadError {
     UnexpectedEof,
     #[error("Received invalid chunk size: {0}")]
     InvalidChunkSize(String),
+    #[error("Received chunk data not terminated by CRLF")]
+    MissingChunkCrlf,
+    #[error("Parse HTTP trailers error: {0}")]
+    ParseTrailers(httparse::Error),
     #[error(transparent)]
     SocketRead(#[from] SocketReadError),
     #[error(transparent)]
@@ -57,7 +66,14 @@ pub enum HttpChunksReadError {
 #[derive(Debug)]
 pub enum HttpChunksReadResult {
     /// The coroutine has successfully terminated its execution.
+    ///
+    /// In streaming mode, `body` is empty: its content has already
+    /// been emitted through [`HttpChunksReadResult::Chunk`].
     Ok { body: Vec<u8> },
+    /// A chunk has been fully decoded (streaming mode only).
+    ///
+    /// Resume the coroutine with `None` to continue decoding.
+    Chunk { chunk: Vec<u8> },
     /// The coroutine encountered an error.
     Err { err: HttpChunksReadError },
     /// The coroutine needs a socket I/O to be performed.
@@ -79,9 +95,18 @@ pub struct HttpChunksRead {
     state: State,
     buffer: Vec<u8>,
     body: Vec<u8>,
+    trailers: Vec<(String, String)>,
+    trailer_bytes: Vec<u8>,
+    streaming: bool,
+    lenient_trailer_eof: bool,
+    max_chunk_size: usize,
+    decoded_len: usize,
 }
 
 impl HttpChunksRead {
+    /// Default upper bound of a chunk size (16 MiB).
+    pub const DEFAULT_MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
     /// Creates a new coroutine from the given [`SocketRead`]
     /// sub-coroutine.
     pub fn new(read: SocketRead) -> Self {
@@ -90,9 +115,108 @@ impl HttpChunksRead {
             state: State::ChunkSize,
             buffer: Vec::new(),
             body: Vec::new(),
+            trailers: Vec::new(),
+            trailer_bytes: Vec::new(),
+            streaming: false,
+            lenient_trailer_eof: false,
+            max_chunk_size: Self::DEFAULT_MAX_CHUNK_SIZE,
+            decoded_len: 0,
         }
     }
 
+    /// Emits each decoded chunk as [`HttpChunksReadResult::Chunk`]
+    /// instead of buffering the whole body.
+    pub fn streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+
+    /// Accepts a body whose last chunk is not followed by the final
+    /// CRLF, when the connection is closed right after it (disabled
+    /// by default).
+    ///
+    /// Some servers send `0\r\n` then close the connection: by
+    /// default this is an [`HttpChunksReadError::UnexpectedEof`],
+    /// whereas the lenient mode returns the body received so far.
+    pub fn lenient_trailer_eof(mut self, lenient: bool) -> Self {
+        self.lenient_trailer_eof = lenient;
+        self
+    }
+
+    /// Sets the upper bound of a chunk size (defaults to
+    /// [`HttpChunksRead::DEFAULT_MAX_CHUNK_SIZE`]).
+    ///
+    /// Each chunk is read at once, so its size, given by the server,
+    /// is bounded in order not to allocate an arbitrary amount of
+    /// memory. A larger chunk fails with
+    /// [`HttpChunksReadError::InvalidChunkSize`].
+    pub fn max_chunk_size(mut self, size: usize) -> Self {
+        self.max_chunk_size = size;
+        self
+    }
+
+    /// Replaces the inner read buffer by one of the given capacity.
+    ///
+    /// Chunk sizes and trailers are read through this buffer, so a
+    /// larger one lets a stream of small chunks be received in fewer
+    /// reads. Defaults to the capacity of the [`SocketRead`] given to
+    /// [`HttpChunksRead::new`].
+    pub fn read_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.read = SocketRead::with_capacity(capacity.max(1));
+        self
+    }
+
+    /// Returns the trailer fields received after the last chunk, as
+    /// `(name, value)` pairs (names stored in lowercase).
+    ///
+    /// The list is only complete once the coroutine has terminated.
+    pub fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    /// Returns the raw trailer section received after the last chunk,
+    /// as sent: each field line with its CRLF, the final empty line
+    /// excluded.
+    ///
+    /// Meant for callers parsing trailers on their own. Empty when no
+    /// trailer field is sent, and only complete once the coroutine has
+    /// terminated.
+    pub fn trailer_bytes(&self) -> &[u8] {
+        &self.trailer_bytes
+    }
+
+    /// Returns the number of body bytes decoded so far.
+    ///
+    /// Chunks emitted in streaming mode are counted as well, which
+    /// makes it suitable for progress reporting between resumes.
+    pub fn decoded_len(&self) -> usize {
+        self.decoded_len
+    }
+
+    /// Resets the coroutine, so that it decodes the next chunked body
+    /// received on the same connection.
+    ///
+    /// The bytes already read past the end of the previous body,
+    /// which belong to the next one, are kept. The body, trailers and
+    /// decoded length are cleared.
+    pub fn reset(&mut self) {
+        self.state = State::ChunkSize;
+        self.body.clear();
+        self.trailers.clear();
+        self.trailer_bytes.clear();
+        self.decoded_len = 0;
+    }
+
+    /// Consumes the coroutine, returning the bytes read past the end
+    /// of the body.
+    ///
+    /// Once the coroutine has terminated, these bytes belong to the
+    /// next message received on the connection, e.g. a pipelined
+    /// response, and must be handed to its reader.
+    pub fn into_leftover(self) -> Vec<u8> {
+        self.buffer
+    }
+
     /// Extends the inner read buffer with the given bytes.
     pub fn extend(&mut self, bytes: impl IntoIterator<Item = u8>) {
         self.buffer.extend(bytes);
@@ -134,9 +258,11 @@ impl HttpChunksRead {
                     // search for potential chunk extension
                     let ext = memchr::memchr(b';', &self.buffer[..crlf]).unwrap_or(crlf);
 
-                    // extract chunk size
+                    // extract chunk size, tolerating trailing whitespace
+                    // sent by some servers before the CRLF
                     let chunk_size = String::from_utf8_lossy(&self.buffer[..ext]);
-                    let Ok(chunk_size) = usize::from_str_radix(&chunk_size, 16) else {
+                    let chunk_size = chunk_size.trim_end_matches(|c: char| c.is_ascii_whitespace());
+                    let Some(chunk_size) = parse_chunk_size(chunk_size, self.max_chunk_size) else {
                         let chunk_size = chunk_size.to_string();
                         return HttpChunksReadResult::Err {
                             err: HttpChunksReadError::InvalidChunkSize(chunk_size),
@@ -179,12 +305,27 @@ impl HttpChunksRead {
                     };
 
                     // buf is exactly chunk_data + CRLF; take only chunk_data
-                    self.body.extend_from_slice(&buf[..*size]);
+                    let size = *size;
+
+                    if buf.get(size..) != Some(&CRLF[..]) {
+                        return HttpChunksReadResult::Err {
+                            err: HttpChunksReadError::MissingChunkCrlf,
+                        };
+                    }
+
                     self.state = State::ChunkSize;
+                    self.decoded_len += size;
+
+                    if self.streaming {
+                        let chunk = buf[..size].to_vec();
+                        break HttpChunksReadResult::Chunk { chunk };
+                    }
+
+                    self.body.extend_from_slice(&buf[..size]);
                 }
                 State::Trailer => {
                     // a double CRLF CRLF means the end of trailer
-                    if memmem::find(&self.buffer, &CRLF_CRLF).is_none() {
+                    let Some(end) = memmem::find(&self.buffer, &CRLF_CRLF) else {
                         let (buf, n) = match self.read.resume(arg.take()) {
                             SocketReadResult::Ok { buf, n } => (buf, n),
                             SocketReadResult::Err { err } => {
@@ -194,6 +335,13 @@ impl HttpChunksRead {
                                 return HttpChunksReadResult::Io { input };
                             }
                             SocketReadResult::Eof => {
+                                // the last chunk is complete, only the
+                                // final CRLF is missing
+                                if self.lenient_trailer_eof && self.buffer.ends_with(&CRLF) {
+                                    self.buffer.extend_from_slice(&CRLF);
+                                    continue;
+                                }
+
                                 return HttpChunksReadResult::Err {
                                     err: HttpChunksReadError::UnexpectedEof,
                                 };
@@ -204,6 +352,37 @@ impl HttpChunksRead {
                         continue;
                     };
 
+                    // trailer section = *( field-line CRLF ) CRLF
+                    if end > 0 {
+                        let section = &self.buffer[CRLF.len()..end + CRLF_CRLF.len()];
+                        self.trailer_bytes = section[..section.len() - CRLF.len()].to_vec();
+                        let mut parsed = [httparse::EMPTY_HEADER; 64];
+
+                        match httparse::parse_headers(section, &mut parsed) {
+                            Ok(httparse::Status::Complete((_, headers))) => {
+                                for header in headers {
+                                    let name = header.name.to_lowercase();
+                                    let value = String::from_utf8_lossy(header.value);
+                                    self.trailers.push((name, value.into_owned()));
+                                }
+                            }
+                            Ok(httparse::Status::Partial) => {
+                                return HttpChunksReadResult::Err {
+                                    err: HttpChunksReadError::UnexpectedEof,
+                                };
+                            }
+                            Err(err) => {
+                                return HttpChunksReadResult::Err {
+                                    err: HttpChunksReadError::ParseTrailers(err),
+                                };
+                            }
+                        }
+                    }
+
+                    // keep the bytes following the body, which may
+                    // belong to a next message
+                    self.buffer.drain(..end + CRLF_CRLF.len());
+
                     break HttpChunksReadResult::Ok {
                         body: mem::take(&mut self.body),
                     };
@@ -212,3 +391,17 @@ impl HttpChunksRead {
         }
     }
 }
+
+/// Parses a chunk size, made of hexadecimal digits only.
+///
+/// Returns `None` for sizes above the given maximum, or that would
+/// overflow once the trailing CRLF is accounted for.
+fn parse_chunk_size(size: &str, max: usize) -> Option<usize> {
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let size = usize::from_str_radix(size, 16).ok()?;
+    size.checked_add(CRLF.len())?;
+    Some(size).filter(|&size| size <= max)
+}
@@ -0,0 +1,97 @@
+//! Blocking iterator over the chunks of a chunked response body.
+//!
+//! [`HttpChunksIter`] drives a [`HttpChunksRead`] coroutine in
+//! streaming mode against a `std` stream, using the
+//! [`io_socket::runtimes::std_stream`] runtime, and yields each
+//! decoded chunk:
+//!
+//! ```rust,ignore
+//! use io_http::rfc9112::{chunk::HttpChunksRead, chunk_iter::HttpChunksIter};
+//! use io_socket::coroutines::read::SocketRead;
+//!
+//! let read = HttpChunksRead::new(SocketRead::default());
+//!
+//! for chunk in HttpChunksIter::new(read, &mut stream) {
+//!     println!("{}", String::from_utf8_lossy(&chunk?));
+//! }
+//! ```
+//!
+//! Requires the `std` cargo feature.
+
+use alloc::{string::String, vec::Vec};
+use std::io::{self, Read, Write};
+
+use io_socket::{io::SocketOutput, runtimes::std_stream::handle};
+use thiserror::Error;
+
+use super::chunk::{HttpChunksRead, HttpChunksReadError, HttpChunksReadResult};
+
+/// Errors that can occur while iterating over chunks.
+#[derive(Debug, Error)]
+pub enum HttpChunksIterError {
+    #[error(transparent)]
+    HttpChunksRead(#[from] HttpChunksReadError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Blocking iterator over the chunks of a chunked body.
+///
+/// The iteration stops after the last chunk, or after the first
+/// error.
+#[derive(Debug)]
+pub struct HttpChunksIter<S> {
+    read: HttpChunksRead,
+    stream: S,
+    arg: Option<SocketOutput>,
+    done: bool,
+}
+
+impl<S: Read + Write> HttpChunksIter<S> {
+    /// Creates a new iterator decoding chunks read from the given
+    /// stream.
+    ///
+    /// The decoder is switched to streaming mode, so that chunks are
+    /// yielded as soon as they are received.
+    pub fn new(read: HttpChunksRead, stream: S) -> Self {
+        Self {
+            read: read.streaming(true),
+            stream,
+            arg: None,
+            done: false,
+        }
+    }
+
+    /// Returns the trailer fields received after the last chunk.
+    ///
+    /// See [`HttpChunksRead::trailers`].
+    pub fn trailers(&self) -> &[(String, String)] {
+        self.read.trailers()
+    }
+}
+
+impl<S: Read + Write> Iterator for HttpChunksIter<S> {
+    type Item = Result<Vec<u8>, HttpChunksIterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read.resume(self.arg.take()) {
+                HttpChunksReadResult::Chunk { chunk } => return Some(Ok(chunk)),
+                HttpChunksReadResult::Ok { .. } => self.done = true,
+                HttpChunksReadResult::Err { err } => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+                HttpChunksReadResult::Io { input } => match handle(&mut self.stream, input) {
+                    Ok(output) => self.arg = Some(output),
+                    Err(err) => {
+                        self.done = true;
+                        return Some(Err(err.into()));
+                    }
+                },
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,91 @@
+//! HTTP/1.1 connection persistence (RFC 9112 §9.3).
+//!
+//! Connection pools need to know whether a connection can carry
+//! another request once a response has been received:
+//!
+//! ```rust
+//! use io_http::rfc9112::connection::keep_alive;
+//!
+//! let headers = [("connection".into(), "close".into())];
+//! assert!(!keep_alive("HTTP/1.1", &headers));
+//! assert!(keep_alive("HTTP/1.1", &[]));
+//! assert!(!keep_alive("HTTP/1.0", &[]));
+//! ```
+
+use alloc::string::String;
+
+use crate::{
+    rfc1945::version::{HTTP_09, HTTP_10},
+    rfc9110::headers::{CONNECTION, has_token},
+};
+
+/// Returns whether the connection can be reused after a message of
+/// the given version and headers.
+///
+/// HTTP/1.1 keeps connections alive unless `Connection: close` is
+/// sent, while HTTP/1.0 closes them unless `Connection: keep-alive`
+/// is sent. The `close` option wins over `keep-alive`, and all the
+/// `Connection` fields are taken into account.
+pub fn keep_alive(version: &str, headers: &[(String, String)]) -> bool {
+    let mut persistent = !matches!(version, HTTP_10 | HTTP_09);
+
+    for (_, value) in headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(CONNECTION))
+    {
+        if has_token(value, "close") {
+            return false;
+        }
+
+        if has_token(value, "keep-alive") {
+            persistent = true;
+        }
+    }
+
+    persistent
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    fn connection(value: &str) -> Vec<(String, String)> {
+        vec![(CONNECTION.into(), value.into())]
+    }
+
+    #[test]
+    fn http11_persistent_by_default() {
+        assert!(keep_alive("HTTP/1.1", &[]));
+        assert!(keep_alive("HTTP/1.1", &connection("keep-alive")));
+        assert!(keep_alive("HTTP/1.1", &connection("Upgrade")));
+    }
+
+    #[test]
+    fn http11_closed_on_close() {
+        assert!(!keep_alive("HTTP/1.1", &connection("close")));
+        assert!(!keep_alive("HTTP/1.1", &connection("Upgrade, Close")));
+    }
+
+    #[test]
+    fn http10_closed_by_default() {
+        assert!(!keep_alive("HTTP/1.0", &[]));
+        assert!(!keep_alive("HTTP/1.0", &connection("close")));
+        assert!(!keep_alive("HTTP/0.9", &[]));
+    }
+
+    #[test]
+    fn http10_persistent_on_keep_alive() {
+        assert!(keep_alive("HTTP/1.0", &connection("keep-alive")));
+        assert!(keep_alive("HTTP/1.0", &connection("Keep-Alive")));
+    }
+
+    #[test]
+    fn close_wins_over_keep_alive() {
+        let mut headers = connection("keep-alive");
+        headers.push(("Connection".into(), "close".into()));
+        assert!(!keep_alive("HTTP/1.0", &headers));
+        assert!(!keep_alive("HTTP/1.1", &connection("keep-alive, close")));
+    }
+}
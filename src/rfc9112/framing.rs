@@ -0,0 +1,154 @@
+//! HTTP/1.1 message body framing (RFC 9112 §6).
+
+use alloc::{string::String, vec::Vec};
+
+/// How a message body is delimited on the wire.
+///
+/// Refs: <https://datatracker.ietf.org/doc/html/rfc9112#section-6.3>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BodyFraming {
+    /// The body length is given by the `Content-Length` header.
+    ContentLength(usize),
+    /// The body is sent using chunked transfer coding.
+    Chunked,
+    /// The body is delimited by the closure of the connection.
+    UntilClose,
+    /// The message has no body (e.g. `204 No Content`).
+    Empty,
+}
+
+/// How a request body is delimited on the wire.
+///
+/// See [`Http11Send::body_framing`](super::send::Http11Send::body_framing).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RequestFraming {
+    /// The body is sent with a `Content-Length`, or using chunked
+    /// transfer coding when the request has trailers.
+    #[default]
+    Auto,
+    /// The body is always sent using chunked transfer coding.
+    Chunked,
+    /// The body is delimited by the closure of the connection.
+    UntilClose,
+}
+
+/// Parses the body length from the given `Content-Length` field
+/// values.
+///
+/// The field may be repeated, or sent as a comma-separated list
+/// (RFC 9112 §6.3): the length is accepted only if all the values
+/// agree.
+///
+/// The length is parsed as a `u64`, so that lengths beyond `usize` on
+/// 32-bit targets are still recognized, and can be rejected rather
+/// than mistaken for an invalid value.
+///
+/// Returns `Ok(None)` when no value is given, and `Err(())` when a
+/// value is not a valid length or when the values disagree: the body
+/// cannot be delimited then, and must not be read until the end of
+/// the connection (RFC 9112 §6.3).
+pub(crate) fn content_length<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> Result<Option<u64>, ()> {
+    let mut length = None;
+
+    for value in values.into_iter().flat_map(|v| v.split(',')) {
+        let value = value.trim();
+
+        // a length is only made of digits, without sign
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(());
+        }
+
+        let Ok(value) = value.parse::<u64>() else {
+            return Err(());
+        };
+
+        match length {
+            Some(length) if length != value => return Err(()),
+            _ => length = Some(value),
+        }
+    }
+
+    Ok(length)
+}
+
+/// Parses the transfer codings from the given `Transfer-Encoding`
+/// field values, in the order they were applied.
+///
+/// The field may be repeated, or sent as a comma-separated list
+/// (RFC 9112 §6.1). Coding names are returned in lowercase, empty
+/// list elements are skipped.
+pub(crate) fn transfer_codings<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    values
+        .into_iter()
+        .flat_map(|v| v.split(','))
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_length_single() {
+        assert_eq!(content_length(["42"]), Ok(Some(42)));
+        assert_eq!(content_length([" 42 "]), Ok(Some(42)));
+    }
+
+    #[test]
+    fn content_length_beyond_u32() {
+        let len = u64::from(u32::MAX) + 1;
+        assert_eq!(content_length(["4294967296"]), Ok(Some(len)));
+        assert_eq!(content_length(["18446744073709551615"]), Ok(Some(u64::MAX)));
+        assert_eq!(content_length(["18446744073709551616"]), Err(()));
+    }
+
+    #[test]
+    fn content_length_absent() {
+        assert_eq!(content_length([]), Ok(None));
+    }
+
+    #[test]
+    fn content_length_invalid() {
+        assert_eq!(content_length(["abc"]), Err(()));
+        assert_eq!(content_length(["-1"]), Err(()));
+        assert_eq!(content_length(["+1"]), Err(()));
+        assert_eq!(content_length([""]), Err(()));
+    }
+
+    #[test]
+    fn content_length_invalid_element() {
+        assert_eq!(content_length(["10, abc"]), Err(()));
+        assert_eq!(content_length(["10", "x"]), Err(()));
+        assert_eq!(content_length(["10,"]), Err(()));
+    }
+
+    #[test]
+    fn content_length_consistent_list() {
+        assert_eq!(content_length(["10, 10"]), Ok(Some(10)));
+        assert_eq!(content_length(["10", "10,10"]), Ok(Some(10)));
+    }
+
+    #[test]
+    fn content_length_conflicting_list() {
+        assert_eq!(content_length(["10, 11"]), Err(()));
+        assert_eq!(content_length(["10", "11"]), Err(()));
+    }
+
+    #[test]
+    fn transfer_codings_list() {
+        assert_eq!(transfer_codings(["chunked"]), ["chunked"]);
+        assert_eq!(transfer_codings(["gzip, Chunked"]), ["gzip", "chunked"]);
+        assert_eq!(transfer_codings(["gzip", " chunked "]), ["gzip", "chunked"]);
+    }
+
+    #[test]
+    fn transfer_codings_empty_elements() {
+        assert!(transfer_codings([]).is_empty());
+        assert!(transfer_codings([" , "]).is_empty());
+        assert_eq!(transfer_codings([", chunked,"]), ["chunked"]);
+    }
+}
@@ -2,11 +2,15 @@
 //!
 //! This module implements the HTTP/1.1 wire protocol as I/O-free
 //! coroutines. No sockets, no async runtime, and no `std` are
-//! required.
+//! required, except for the blocking [`chunk_iter`] adapter.
 //!
 //! Shared types (status codes, headers, request, response) live in
 //! [`crate::rfc9110`].
 
 pub mod chunk;
+#[cfg(feature = "std")]
+pub mod chunk_iter;
+pub mod connection;
+pub mod framing;
 pub mod send;
 pub mod version;
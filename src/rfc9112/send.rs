@@ -11,29 +11,58 @@
 //! | Chunked      | `Transfer-Encoding: chunked` |
 //! | Fixed-length | `Content-Length: <n>`        |
 //! | Read-to-EOF  | Neither header present       |
+//!
+//! The request body is sent with a `Content-Length` header, unless
+//! the request carries trailer fields: the body is then sent using
+//! chunked transfer coding, followed by the trailers.
+//!
+//! A chunked response body may also be compressed using a `gzip` or
+//! `deflate` transfer coding applied before chunking (e.g.
+//! `Transfer-Encoding: gzip, chunked`). Such a body is de-chunked,
+//! then decompressed, which requires the `compression` cargo
+//! feature.
 
-use alloc::{format, string::String, vec, vec::Vec};
-use core::mem;
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec, vec::Vec};
+use core::{fmt, mem, time::Duration};
 
 use io_socket::{
     coroutines::{read::*, read_exact::*, read_to_end::*, write::*},
     io::{SocketInput, SocketOutput},
 };
-use log::{Level, info, log_enabled, trace};
 use thiserror::Error;
 use url::Url;
 
+#[cfg(feature = "compression")]
+use crate::rfc9110::coding::{
+    ContentCoding, ContentDecode, ContentDecodeError, ContentDecodeResult,
+};
 use crate::{
-    rfc1945::version::HTTP_10,
+    info,
+    rfc1945::version::{HTTP_09, HTTP_10},
     rfc9110::{
-        headers::{CONNECTION, CONTENT_LENGTH, LOCATION, TRANSFER_ENCODING},
+        headers::{
+            CONNECTION, CONTENT_LENGTH, FORBIDDEN_TRAILERS, HOP_BY_HOP_HEADERS, HOST, LOCATION,
+            SENSITIVE_HEADERS, TRAILER, TRANSFER_ENCODING, is_token,
+        },
+        redirect::{RedirectAction, RedirectPolicy},
         request::HttpRequest,
         response::{HttpResponse, ResponseBuilder},
         status::StatusCode,
+        uri::default_port,
+    },
+    rfc9112::{
+        chunk::*,
+        connection::keep_alive,
+        framing::{BodyFraming, RequestFraming, content_length, transfer_codings},
+        version::HTTP_11,
     },
-    rfc9112::{chunk::*, version::HTTP_11},
+    trace,
 };
 
+/// Methods for which a request body has no defined semantics (RFC
+/// 9110 §9.3).
+const BODYLESS_METHODS: [&str; 5] = ["GET", "HEAD", "DELETE", "OPTIONS", "TRACE"];
+
 const CR: u8 = b'\r';
 const CRLF: [u8; 2] = [CR, LF];
 const LF: u8 = b'\n';
@@ -41,13 +70,80 @@ const SP: u8 = b' ';
 
 const CRLF_CRLF: [u8; 4] = [CR, LF, CR, LF];
 
+/// Maximum length of each write of a request body sent apart from
+/// its headers (64 KiB).
+const MAX_BODY_WRITE_LEN: usize = 64 * 1024;
+
+/// Phase of the exchange performed by [`Http11Send`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Http11SendPhase {
+    /// Writing the request.
+    Sending,
+    /// Reading the response status line and headers.
+    ReceivingHeaders,
+    /// Reading the response body.
+    ReceivingBody,
+}
+
+impl fmt::Display for Http11SendPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sending => write!(f, "sending request"),
+            Self::ReceivingHeaders => write!(f, "receiving response headers"),
+            Self::ReceivingBody => write!(f, "receiving response body"),
+        }
+    }
+}
+
 /// Errors that can occur during the coroutine progression.
 #[derive(Debug, Error)]
 pub enum Http11SendError {
-    #[error("Received unexpected EOF")]
-    UnexpectedEof,
+    /// The connection was closed before the end of the exchange.
+    ///
+    /// `bytes_so_far` counts the bytes sent or received during
+    /// `phase`: the request bytes written while sending, the response
+    /// bytes read while receiving.
+    #[error("Received unexpected EOF while {phase} after {bytes_so_far} bytes")]
+    UnexpectedEof {
+        phase: Http11SendPhase,
+        bytes_so_far: usize,
+    },
     #[error("Parse HTTP response headers error: {0}")]
     ParseResponseHeaders(httparse::Error),
+    #[error("Received keep-alive response without Content-Length nor Transfer-Encoding")]
+    UnboundedBodyOnKeepAlive,
+    #[error("Invalid HTTP method {0:?}")]
+    InvalidMethod(String),
+    #[error("Field {0} is not allowed in request trailers")]
+    ForbiddenTrailer(String),
+    #[error("Host {0} does not match the request URL authority")]
+    HostMismatch(String),
+    #[error("Request has more than one Host field")]
+    DuplicateHost,
+    #[error("Field {0} does not match the framing of the request body")]
+    FramingFieldMismatch(String),
+    #[error("Received TRACE echo not matching the request sent")]
+    TraceMismatch,
+    #[error("Received invalid or conflicting Content-Length values")]
+    AmbiguousBodyFraming,
+    #[error("Received Content-Length {0} exceeding the addressable memory")]
+    ContentLengthTooLarge(u64),
+    #[error("Received obsolete line folding in header {0}")]
+    ObsoleteLineFolding(String),
+    #[error("Received Transfer-Encoding without chunked as final coding")]
+    ChunkedNotLast,
+    #[error("Received unsupported transfer coding {0}")]
+    UnsupportedTransferCoding(String),
+    #[error("Refused insecure redirect to {0}")]
+    InsecureRedirect(Url),
+    #[error("Refused redirect to {0}")]
+    RedirectRefused(Url),
+    #[error("Refused redirect to unsupported scheme {0}")]
+    UnsupportedRedirectScheme(String),
+    #[error("Exceeded the deadline of the request")]
+    DeadlineExceeded,
+    #[error("Push response body to sink error")]
+    BodySink(#[source] Box<dyn core::error::Error + Send + Sync>),
     #[error(transparent)]
     SocketRead(#[from] SocketReadError),
     #[error(transparent)]
@@ -58,6 +154,9 @@ pub enum Http11SendError {
     SocketReadToEnd(#[from] SocketReadToEndError),
     #[error(transparent)]
     SocketWrite(#[from] SocketWriteError),
+    #[cfg(feature = "compression")]
+    #[error(transparent)]
+    ContentDecode(#[from] ContentDecodeError),
 }
 
 /// Result returned by [`Http11Send::resume`].
@@ -79,6 +178,21 @@ pub enum Http11SendResult {
         /// When `false`, the caller must open a new connection before
         /// sending another request.
         keep_alive: bool,
+        /// How the response body was delimited by the server.
+        framing: BodyFraming,
+        /// Whether the server responded with `304 Not Modified` to a
+        /// conditional request (RFC 9110 §15.4.5).
+        ///
+        /// When `true`, the cached representation is still valid.
+        not_modified: bool,
+        /// Whether the body delimited by the connection closure ended
+        /// with an abrupt close, signaled by
+        /// [`Http11Send::close_abruptly`].
+        ///
+        /// When `true`, the body may be truncated: security-sensitive
+        /// callers should reject it. Always `false` for other
+        /// framings.
+        truncated: bool,
     },
 
     /// The coroutine needs a socket I/O to be performed.
@@ -106,6 +220,21 @@ pub enum Http11SendResult {
         same_origin: bool,
     },
 
+    /// The server accepted a `CONNECT` request with a 2xx response.
+    ///
+    /// The response has no body: the connection now carries the
+    /// tunneled stream, whose first bytes may already have been
+    /// received along with the response headers.
+    Tunnel {
+        /// The `CONNECT` request that was sent.
+        request: HttpRequest,
+        /// The 2xx response received.
+        response: HttpResponse,
+        /// Bytes of the tunneled stream received after the response
+        /// headers.
+        buf: Vec<u8>,
+    },
+
     /// The coroutine encountered an error.
     Err { err: Http11SendError },
 }
@@ -117,10 +246,33 @@ enum State {
     Serialize,
 
     /// Send the serialized request bytes.
-    Send(SocketWrite),
+    ///
+    /// A plain body is not appended whole to the headers: it stays
+    /// in the request, and is copied after them by slices of at most
+    /// [`MAX_BODY_WRITE_LEN`] bytes into the reused write buffer, so
+    /// that the request keeps it whatever happens to the writes. A
+    /// short write is resumed from the first byte not written.
+    Send {
+        write: SocketWrite,
+        has_body: bool,
+        /// Offset in the body of the first byte of the write buffer,
+        /// `None` while the headers are being written.
+        offset: Option<usize>,
+        /// Number of request bytes written so far.
+        written: usize,
+    },
 
     /// Receive response headers incrementally.
-    ReceiveHeaders { read: SocketRead, headers: Vec<u8> },
+    ///
+    /// The headers are only parsed once their terminating empty line
+    /// has been received. Until then, each read only scans the new
+    /// bytes for it, from the `scanned` offset, so that headers
+    /// received in many small reads are not reparsed from scratch.
+    ReceiveHeaders {
+        read: SocketRead,
+        headers: Vec<u8>,
+        scanned: usize,
+    },
 
     /// Receive the response body using chunked transfer coding.
     ///
@@ -133,6 +285,19 @@ enum State {
         response: ResponseBuilder,
     },
 
+    /// Receive a compressed response body using chunked transfer
+    /// coding.
+    ///
+    /// Used when the `Transfer-Encoding` response header lists a
+    /// `gzip` or `deflate` coding before `chunked`.
+    ///
+    /// Refs: <https://datatracker.ietf.org/doc/html/rfc9112#section-7>
+    #[cfg(feature = "compression")]
+    ReceiveDecodedChunkedBody {
+        read: ContentDecode,
+        response: ResponseBuilder,
+    },
+
     /// Receive a fixed-length response body.
     ///
     /// Used when the `Content-Length` response header is present and
@@ -140,8 +305,12 @@ enum State {
     ///
     /// Refs: <https://datatracker.ietf.org/doc/html/rfc9112#body.content-length>
     ReceiveLengthedBody {
-        read: SocketReadExact,
+        read: SocketRead,
         response: ResponseBuilder,
+        body: Vec<u8>,
+        len: usize,
+        /// Bytes of the body already pushed into the body sink.
+        sunk: usize,
     },
 
     /// Receive the response body until EOF.
@@ -149,11 +318,361 @@ enum State {
     /// Fallback when neither `Transfer-Encoding` nor `Content-Length`
     /// is present or valid.
     ReceiveBody {
-        read: SocketReadToEnd,
+        read: SocketRead,
         response: ResponseBuilder,
+        body: Vec<u8>,
     },
 }
 
+/// Monotonic clock supplied by the caller, so that the coroutine can
+/// enforce a deadline without accessing the system time.
+///
+/// See [`Http11Send::with_deadline`].
+pub trait Clock {
+    /// Returns the time elapsed since an arbitrary origin, which must
+    /// never go backward.
+    fn now(&self) -> Duration;
+}
+
+/// Canonical view of a request about to be sent, given to the
+/// [signer](Http11Send::sign_with).
+///
+/// The components are the ones written on the wire, so that a
+/// signature computed over them is verifiable by the server.
+#[derive(Clone, Copy, Debug)]
+pub struct RequestComponents<'a> {
+    /// The request method (e.g. `POST`).
+    pub method: &'a str,
+    /// The request target, as written on the request line (e.g.
+    /// `/path?query`).
+    pub target: &'a str,
+    /// The header fields, in the order and with the casing they are
+    /// written, framing fields included.
+    pub headers: &'a [(&'a str, &'a [u8])],
+}
+
+impl RequestComponents<'_> {
+    /// Returns the value of the first header field with the given
+    /// name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+}
+
+type SignerFn = dyn Fn(&RequestComponents) -> Vec<(String, String)> + Send + Sync;
+
+/// User-supplied request signer.
+#[derive(Clone)]
+struct Signer(Arc<SignerFn>);
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Signer(..)")
+    }
+}
+
+/// Clock and time after which the coroutine fails.
+#[derive(Clone)]
+struct Deadline {
+    clock: Arc<dyn Clock + Send + Sync>,
+    at: Duration,
+}
+
+impl fmt::Debug for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deadline").field("at", &self.at).finish()
+    }
+}
+
+type BodySinkFn = dyn Fn(&[u8]) -> Result<(), Http11SendError> + Send + Sync;
+
+/// User-supplied sink receiving the response body.
+#[derive(Clone)]
+struct BodySink(Arc<BodySinkFn>);
+
+impl fmt::Debug for BodySink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodySink(..)")
+    }
+}
+
+/// Reusable configuration of [`Http11Send`] coroutines.
+///
+/// The builder holds the same options as the [`Http11Send`] setters,
+/// and can be cloned to spawn many coroutines from a single template:
+///
+/// ```rust,ignore
+/// use io_http::rfc9112::send::Http11SendBuilder;
+///
+/// let builder = Http11SendBuilder::new().strict(true).merge_trailers(true);
+///
+/// let send_a = builder.build(request_a);
+/// let send_b = builder.build(request_b);
+/// ```
+///
+/// The read buffer given to [`Http11Send::with_read_buffer`] and the
+/// fields given to [`Http11Send::with_ordered_headers`] belong to a
+/// single request instead: they are not held by the builder, hence
+/// not kept by [`Http11Send::follow`].
+#[derive(Clone, Debug)]
+pub struct Http11SendBuilder {
+    strict: bool,
+    forbid_https_downgrade: bool,
+    redirect_policy: Option<RedirectPolicy>,
+    body_framing: RequestFraming,
+    merge_trailers: bool,
+    always_send_content_length: bool,
+    asterisk_form: bool,
+    max_read_buffer_capacity: usize,
+    max_chunk_size: usize,
+    #[cfg(feature = "compression")]
+    max_decode_ratio: Option<usize>,
+    #[cfg(feature = "compression")]
+    max_decoded_len: Option<usize>,
+    early_response: bool,
+    capture_request_bytes: bool,
+    canonical_headers: bool,
+    capture_raw_headers: bool,
+    validate_host: bool,
+    verify_trace: bool,
+    allow_http09: bool,
+    #[cfg_attr(not(feature = "log"), allow(dead_code))]
+    hex_trace_headers: bool,
+    strip_hop_by_hop: bool,
+    rewrite_framing_headers: bool,
+    body_sink: Option<BodySink>,
+    deadline: Option<Deadline>,
+    signer: Option<Signer>,
+}
+
+impl Http11SendBuilder {
+    /// Creates a new builder with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            strict: false,
+            forbid_https_downgrade: false,
+            redirect_policy: None,
+            body_framing: RequestFraming::Auto,
+            merge_trailers: false,
+            always_send_content_length: false,
+            asterisk_form: false,
+            max_read_buffer_capacity: Http11Send::DEFAULT_MAX_READ_BUFFER_CAPACITY,
+            max_chunk_size: HttpChunksRead::DEFAULT_MAX_CHUNK_SIZE,
+            #[cfg(feature = "compression")]
+            max_decode_ratio: None,
+            #[cfg(feature = "compression")]
+            max_decoded_len: None,
+            early_response: false,
+            capture_request_bytes: false,
+            canonical_headers: false,
+            capture_raw_headers: false,
+            validate_host: false,
+            verify_trace: false,
+            allow_http09: false,
+            hex_trace_headers: false,
+            strip_hop_by_hop: false,
+            rewrite_framing_headers: false,
+            body_sink: None,
+            deadline: None,
+            signer: None,
+        }
+    }
+
+    /// See [`Http11Send::strict`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// See [`Http11Send::forbid_https_downgrade`].
+    pub fn forbid_https_downgrade(mut self, forbid: bool) -> Self {
+        self.forbid_https_downgrade = forbid;
+        self
+    }
+
+    /// See [`Http11Send::redirect_policy`].
+    pub fn redirect_policy(
+        mut self,
+        f: impl Fn(&HttpRequest, &HttpResponse, &Url) -> RedirectAction + Send + Sync + 'static,
+    ) -> Self {
+        self.redirect_policy = Some(RedirectPolicy::new(f));
+        self
+    }
+
+    /// See [`Http11Send::body_framing`].
+    pub fn body_framing(mut self, framing: RequestFraming) -> Self {
+        self.body_framing = framing;
+        self
+    }
+
+    /// See [`Http11Send::merge_trailers`].
+    pub fn merge_trailers(mut self, merge: bool) -> Self {
+        self.merge_trailers = merge;
+        self
+    }
+
+    /// See [`Http11Send::always_send_content_length`].
+    pub fn always_send_content_length(mut self, always: bool) -> Self {
+        self.always_send_content_length = always;
+        self
+    }
+
+    /// See [`Http11Send::asterisk_form`].
+    pub fn asterisk_form(mut self, asterisk_form: bool) -> Self {
+        self.asterisk_form = asterisk_form;
+        self
+    }
+
+    /// See [`Http11Send::max_read_buffer_capacity`].
+    pub fn max_read_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.max_read_buffer_capacity = capacity.max(1);
+        self
+    }
+
+    /// See [`Http11Send::max_chunk_size`].
+    pub fn max_chunk_size(mut self, size: usize) -> Self {
+        self.max_chunk_size = size;
+        self
+    }
+
+    /// See [`Http11Send::max_decode_ratio`].
+    #[cfg(feature = "compression")]
+    pub fn max_decode_ratio(mut self, ratio: usize) -> Self {
+        self.max_decode_ratio = Some(ratio);
+        self
+    }
+
+    /// See [`Http11Send::max_decoded_len`].
+    #[cfg(feature = "compression")]
+    pub fn max_decoded_len(mut self, len: usize) -> Self {
+        self.max_decoded_len = Some(len);
+        self
+    }
+
+    /// See [`Http11Send::early_response`].
+    pub fn early_response(mut self, early_response: bool) -> Self {
+        self.early_response = early_response;
+        self
+    }
+
+    /// See [`Http11Send::capture_request_bytes`].
+    pub fn capture_request_bytes(mut self, capture: bool) -> Self {
+        self.capture_request_bytes = capture;
+        self
+    }
+
+    /// See [`Http11Send::canonical_headers`].
+    pub fn canonical_headers(mut self, canonical: bool) -> Self {
+        self.canonical_headers = canonical;
+        self
+    }
+
+    /// See [`Http11Send::capture_raw_headers`].
+    pub fn capture_raw_headers(mut self, capture: bool) -> Self {
+        self.capture_raw_headers = capture;
+        self
+    }
+
+    /// See [`Http11Send::validate_host`].
+    pub fn validate_host(mut self, validate: bool) -> Self {
+        self.validate_host = validate;
+        self
+    }
+
+    /// See [`Http11Send::verify_trace`].
+    pub fn verify_trace(mut self, verify: bool) -> Self {
+        self.verify_trace = verify;
+        self
+    }
+
+    /// See [`Http11Send::allow_http09`].
+    pub fn allow_http09(mut self, allow: bool) -> Self {
+        self.allow_http09 = allow;
+        self
+    }
+
+    /// See [`Http11Send::hex_trace_headers`].
+    pub fn hex_trace_headers(mut self, hex: bool) -> Self {
+        self.hex_trace_headers = hex;
+        self
+    }
+
+    /// See [`Http11Send::strip_hop_by_hop`].
+    pub fn strip_hop_by_hop(mut self, strip: bool) -> Self {
+        self.strip_hop_by_hop = strip;
+        self
+    }
+
+    /// See [`Http11Send::rewrite_framing_headers`].
+    pub fn rewrite_framing_headers(mut self, rewrite: bool) -> Self {
+        self.rewrite_framing_headers = rewrite;
+        self
+    }
+
+    /// See [`Http11Send::body_sink`].
+    pub fn body_sink<E>(
+        mut self,
+        f: impl Fn(&[u8]) -> Result<(), E> + Send + Sync + 'static,
+    ) -> Self
+    where
+        E: Into<Box<dyn core::error::Error + Send + Sync>>,
+    {
+        let f = move |bytes: &[u8]| f(bytes).map_err(|err| Http11SendError::BodySink(err.into()));
+        self.body_sink = Some(BodySink(Arc::new(f)));
+        self
+    }
+
+    /// See [`Http11Send::with_deadline`].
+    pub fn with_deadline(
+        mut self,
+        clock: impl Clock + Send + Sync + 'static,
+        deadline: Duration,
+    ) -> Self {
+        let clock = Arc::new(clock);
+        self.deadline = Some(Deadline {
+            clock,
+            at: deadline,
+        });
+        self
+    }
+
+    /// See [`Http11Send::sign_with`].
+    pub fn sign_with(
+        mut self,
+        f: impl Fn(&RequestComponents) -> Vec<(String, String)> + Send + Sync + 'static,
+    ) -> Self {
+        self.signer = Some(Signer(Arc::new(f)));
+        self
+    }
+
+    /// Creates a new coroutine that will send the given request, using
+    /// this configuration.
+    pub fn build(&self, request: HttpRequest) -> Http11Send {
+        Http11Send {
+            request: Some(request),
+            state: State::Serialize,
+            is_conn_closed: false,
+            config: self.clone(),
+            request_bytes: None,
+            raw_headers: None,
+            read_buffer: None,
+            read_buffer_capacity: None,
+            is_truncated: false,
+            body_sink: None,
+            ordered_headers: None,
+        }
+    }
+}
+
+impl Default for Http11SendBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// I/O-free coroutine to send an HTTP/1.1 request and receive its response.
 ///
 /// # Example
@@ -183,8 +702,9 @@ enum State {
 ///             if !keep_alive || !same_origin {
 ///                 stream = TcpStream::connect(new_url.host_str().unwrap()).unwrap();
 ///             }
-///             send = Http11Send::new(HttpRequest::get(new_url));
+///             send = send.follow(HttpRequest::get(new_url));
 ///         }
+///         Http11SendResult::Tunnel { .. } => unreachable!("CONNECT request not sent"),
 ///     }
 /// };
 ///
@@ -198,206 +718,1142 @@ pub struct Http11Send {
     request: Option<HttpRequest>,
     state: State,
     is_conn_closed: bool,
+    config: Http11SendBuilder,
+    request_bytes: Option<Vec<u8>>,
+    raw_headers: Option<Vec<(String, Vec<u8>)>>,
+    read_buffer: Option<Vec<u8>>,
+    read_buffer_capacity: Option<usize>,
+    is_truncated: bool,
+    body_sink: Option<BodySink>,
+    ordered_headers: Option<Vec<(String, Vec<u8>)>>,
 }
 
 impl Http11Send {
+    /// Default upper bound of the read buffer used to receive a
+    /// fixed-length response body (64 KiB).
+    pub const DEFAULT_MAX_READ_BUFFER_CAPACITY: usize = 64 * 1024;
+
     /// Creates a new coroutine that will send the given request and
     /// receive its response.
+    ///
+    /// Use [`Http11SendBuilder`] to create several coroutines sharing
+    /// the same configuration.
     pub fn new(request: HttpRequest) -> Self {
-        Self {
-            request: Some(request),
-            state: State::Serialize,
-            is_conn_closed: false,
-        }
+        Http11SendBuilder::new().build(request)
     }
 
-    /// Advances the coroutine.
+    /// Creates a new coroutine that will send the given request, and
+    /// receive its response using the given read buffer.
     ///
-    /// Pass `None` on the first call. On subsequent calls, pass the
-    /// [`SocketOutput`] returned by the runtime after processing the
-    /// last emitted [`SocketInput`].
-    pub fn resume(&mut self, mut arg: Option<SocketOutput>) -> Http11SendResult {
-        if arg.is_none() {
-            info!("send HTTP/1.1 request");
-        }
+    /// The buffer is used up to its capacity, which must not be zero,
+    /// instead of allocating one internally. It can be taken back with
+    /// [`Http11Send::take_read_buffer`] once the response is received,
+    /// in order to reuse it for the next request.
+    pub fn with_read_buffer(request: HttpRequest, buf: Vec<u8>) -> Self {
+        let mut send = Self::new(request);
+        send.read_buffer_capacity = Some(buf.capacity()).filter(|&capacity| capacity > 0);
+        send.read_buffer = Some(buf);
+        send
+    }
 
-        loop {
-            match &mut self.state {
-                State::Serialize => {
-                    let req = self.request.as_ref().unwrap();
-                    trace!("HTTP/1.1 request: {req:?}");
+    /// Creates a new coroutine that will send the given request with
+    /// the given header fields, instead of the request ones.
+    ///
+    /// The fields are written verbatim, in order and with their exact
+    /// casing, as required by signing schemes covering them (e.g.
+    /// HTTP Message Signatures), even when
+    /// [`Http11Send::canonical_headers`] is enabled.
+    ///
+    /// The framing fields (`Content-Length`, `Transfer-Encoding`,
+    /// `Trailer`, and `Connection` when the body is delimited by
+    /// closing the connection) are generated after the list. The list
+    /// can contain them in order to sign them, as long as they match
+    /// the generated ones, which are then not repeated; otherwise the
+    /// coroutine fails with [`Http11SendError::FramingFieldMismatch`].
+    ///
+    /// The fields belong to this request only: they are not kept by
+    /// [`Http11Send::follow`], so the next request of a redirect chain
+    /// is sent with its own headers, and must be given its ordered
+    /// fields again if needed.
+    pub fn with_ordered_headers(request: HttpRequest, headers: Vec<(String, Vec<u8>)>) -> Self {
+        let mut send = Self::new(request);
+        send.ordered_headers = Some(headers);
+        send
+    }
 
-                    let mut bytes = Vec::new();
+    /// Creates a new coroutine that will send the given request, with
+    /// the same configuration as this one.
+    ///
+    /// Meant to send the next request of a redirect chain, possibly
+    /// on a new connection, without losing the options set on the
+    /// first coroutine. The read buffer and the ordered fields, given
+    /// to the first request only, are not kept.
+    pub fn follow(&self, request: HttpRequest) -> Self {
+        self.config.build(request)
+    }
 
-                    bytes.extend(req.method.as_bytes());
-                    bytes.push(SP);
-                    bytes.extend(req.url.path().as_bytes());
+    /// Enables or disables strict mode (disabled by default).
+    ///
+    /// In strict mode, a keep-alive response carrying neither
+    /// `Content-Length` nor `Transfer-Encoding` fails with
+    /// [`Http11SendError::UnboundedBodyOnKeepAlive`] instead of being
+    /// read until EOF, which would hang on a persistent connection.
+    ///
+    /// A header value folded over several lines also fails, with
+    /// [`Http11SendError::ObsoleteLineFolding`], instead of being
+    /// unfolded into a single line.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config = self.config.strict(strict);
+        self
+    }
 
-                    if let Some(q) = req.url.query() {
-                        bytes.extend(b"?");
-                        bytes.extend(q.as_bytes());
-                    }
+    /// Forbids or allows redirects from `https` to `http` (allowed by
+    /// default).
+    ///
+    /// When forbidden, such a redirect fails with
+    /// [`Http11SendError::InsecureRedirect`] instead of being
+    /// surfaced as [`Http11SendResult::Redirect`].
+    pub fn forbid_https_downgrade(mut self, forbid: bool) -> Self {
+        self.config = self.config.forbid_https_downgrade(forbid);
+        self
+    }
 
-                    bytes.push(SP);
-                    bytes.extend(HTTP_11.as_bytes());
-                    bytes.extend(CRLF);
+    /// Sets a predicate deciding what to do with each redirect.
+    ///
+    /// The predicate is invoked with the request, the 3xx response and
+    /// the resolved target URL, after the built-in checks. By default,
+    /// all redirects are surfaced as [`Http11SendResult::Redirect`].
+    pub fn redirect_policy(
+        mut self,
+        f: impl Fn(&HttpRequest, &HttpResponse, &Url) -> RedirectAction + Send + Sync + 'static,
+    ) -> Self {
+        self.config = self.config.redirect_policy(f);
+        self
+    }
 
-                    for (key, val) in &req.headers {
-                        // skip content-length, as it is automatically
-                        // generated below
-                        if key.eq_ignore_ascii_case(CONTENT_LENGTH) {
-                            continue;
-                        }
+    /// Selects how the request body is delimited
+    /// ([`RequestFraming::Auto`] by default).
+    ///
+    /// - [`RequestFraming::Auto`] sends the body with a
+    ///   `Content-Length`, or using chunked transfer coding when the
+    ///   request has trailers.
+    /// - [`RequestFraming::Chunked`] always sends the body using
+    ///   chunked transfer coding.
+    /// - [`RequestFraming::UntilClose`] sends the body with
+    ///   `Connection: close` and no framing header at all. The caller
+    ///   is then responsible for closing the write half of the
+    ///   connection, which cannot be reused. Ignored when the request
+    ///   has trailers.
+    pub fn body_framing(mut self, framing: RequestFraming) -> Self {
+        self.config = self.config.body_framing(framing);
+        self
+    }
 
-                        bytes.extend(key.as_bytes());
-                        bytes.extend(b": ");
-                        bytes.extend(val.as_bytes());
-                        bytes.extend(CRLF);
-                    }
+    /// Merges the trailer fields of a chunked response into its
+    /// headers (disabled by default).
+    ///
+    /// Fields that are not allowed as trailers are dropped. When
+    /// disabled, trailer fields are kept apart in
+    /// [`HttpResponse::trailers`].
+    pub fn merge_trailers(mut self, merge: bool) -> Self {
+        self.config = self.config.merge_trailers(merge);
+        self
+    }
 
-                    let body_len = format!("{}", req.body.len());
-                    bytes.extend(CONTENT_LENGTH.as_bytes());
-                    bytes.extend(b": ");
-                    bytes.extend(body_len.as_bytes());
-                    bytes.extend(CRLF_CRLF);
-                    bytes.extend(&req.body);
+    /// Sends a `Content-Length` even for an empty body on methods
+    /// that do not expect one (disabled by default).
+    ///
+    /// By default, `Content-Length: 0` is omitted for `GET`, `HEAD`,
+    /// `DELETE`, `OPTIONS` and `TRACE` requests without body, as some
+    /// strict servers reject it.
+    pub fn always_send_content_length(mut self, always: bool) -> Self {
+        self.config = self.config.always_send_content_length(always);
+        self
+    }
 
-                    self.state = State::Send(SocketWrite::new(bytes));
-                }
-                State::Send(write) => {
-                    match write.resume(arg.take()) {
-                        SocketWriteResult::Ok { .. } => (),
-                        SocketWriteResult::Err { err } => {
-                            return Http11SendResult::Err { err: err.into() };
-                        }
-                        SocketWriteResult::Io { input } => {
-                            return Http11SendResult::Io { input };
-                        }
-                        SocketWriteResult::Eof => {
-                            return Http11SendResult::Err {
-                                err: Http11SendError::UnexpectedEof,
-                            };
-                        }
-                    };
+    /// Sends the request target in asterisk-form (disabled by
+    /// default).
+    ///
+    /// When enabled on an `OPTIONS` request, the request line targets
+    /// the server as a whole (`OPTIONS * HTTP/1.1`) instead of the URL
+    /// path. Ignored for other methods.
+    pub fn asterisk_form(mut self, asterisk_form: bool) -> Self {
+        self.config = self.config.asterisk_form(asterisk_form);
+        self
+    }
 
-                    trace!("resume after sending HTTP/1.1 request");
+    /// Sets the upper bound of the read buffer used to receive a
+    /// fixed-length response body (defaults to
+    /// [`Http11Send::DEFAULT_MAX_READ_BUFFER_CAPACITY`]).
+    ///
+    /// The buffer is sized toward the remaining `Content-Length`, so
+    /// that a large body is received in fewer reads, without ever
+    /// reading past the end of the body.
+    pub fn max_read_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.config = self.config.max_read_buffer_capacity(capacity);
+        self
+    }
 
-                    self.state = State::ReceiveHeaders {
-                        read: SocketRead::default(),
-                        headers: Vec::new(),
-                    };
-                }
-                State::ReceiveHeaders { read, headers } => {
-                    let (buf, n) = match read.resume(arg.take()) {
-                        SocketReadResult::Ok { buf, n } => (buf, n),
-                        SocketReadResult::Err { err } => {
-                            return Http11SendResult::Err { err: err.into() };
-                        }
-                        SocketReadResult::Io { input } => {
-                            return Http11SendResult::Io { input };
-                        }
-                        SocketReadResult::Eof => {
-                            return Http11SendResult::Err {
-                                err: Http11SendError::UnexpectedEof,
-                            };
-                        }
-                    };
+    /// Sets the upper bound of a chunk size of a chunked response
+    /// body (defaults to [`HttpChunksRead::DEFAULT_MAX_CHUNK_SIZE`]).
+    ///
+    /// See [`HttpChunksRead::max_chunk_size`].
+    pub fn max_chunk_size(mut self, size: usize) -> Self {
+        self.config = self.config.max_chunk_size(size);
+        self
+    }
 
-                    trace!("resume after receiving partial HTTP/1.1 response headers");
+    /// Sets the maximum ratio between the size of a body decoded from
+    /// a `gzip` or `deflate` transfer coding and its compressed size
+    /// (disabled by default).
+    ///
+    /// See [`ContentDecode::max_ratio`].
+    #[cfg(feature = "compression")]
+    pub fn max_decode_ratio(mut self, ratio: usize) -> Self {
+        self.config = self.config.max_decode_ratio(ratio);
+        self
+    }
 
-                    headers.extend_from_slice(&buf[..n]);
+    /// Sets the maximum size of a body decoded from a `gzip` or
+    /// `deflate` transfer coding (disabled by default).
+    ///
+    /// See [`ContentDecode::max_decoded_len`].
+    #[cfg(feature = "compression")]
+    pub fn max_decoded_len(mut self, len: usize) -> Self {
+        self.config = self.config.max_decoded_len(len);
+        self
+    }
 
-                    let mut parsed = [httparse::EMPTY_HEADER; 64];
-                    let mut parsed = httparse::Response::new(&mut parsed);
+    /// Reads the response when the server stops accepting the request
+    /// (disabled by default).
+    ///
+    /// Some servers send a response, typically an error, then close
+    /// their read half before the request body is fully written.
+    /// When enabled, a write reaching EOF switches the coroutine to
+    /// receiving that response instead of failing with
+    /// [`Http11SendError::UnexpectedEof`].
+    ///
+    /// See also [`Http11Send::receive_response`], for runtimes
+    /// surfacing such a write as an error (e.g. broken pipe).
+    pub fn early_response(mut self, early_response: bool) -> Self {
+        self.config = self.config.early_response(early_response);
+        self
+    }
 
-                    let n = match parsed.parse(headers) {
-                        Ok(httparse::Status::Complete(n)) => n,
-                        Ok(httparse::Status::Partial) => {
-                            trace!(
-                                "received incomplete HTTP/1.1 response headers, need more bytes"
-                            );
-                            read.replace(buf);
-                            continue;
-                        }
-                        Err(err) => {
-                            return Http11SendResult::Err {
-                                err: Http11SendError::ParseResponseHeaders(err),
-                            };
-                        }
-                    };
+    /// Keeps a copy of the serialized request (disabled by default).
+    ///
+    /// When enabled, the exact bytes written to the socket, request
+    /// line, headers and body included, are exposed through
+    /// [`Http11Send::request_bytes`], e.g. for audit logs or request
+    /// signing checks. Disabled by default to spare the copy.
+    pub fn capture_request_bytes(mut self, capture: bool) -> Self {
+        self.config = self.config.capture_request_bytes(capture);
+        self
+    }
 
-                    if log_enabled!(Level::Trace) {
-                        let h = String::from_utf8_lossy(&headers[..n]);
-                        trace!("HTTP/1.1 response headers:\n{h}");
-                    }
+    /// Sends header names lowercased and sorted (disabled by
+    /// default).
+    ///
+    /// When enabled, the header section is emitted in a canonical
+    /// form suitable for request signing: names are lowercased, and
+    /// fields are sorted by name, repeated fields keeping their
+    /// relative order. Values are left untouched. When disabled,
+    /// headers are sent as given, framing headers last.
+    pub fn canonical_headers(mut self, canonical: bool) -> Self {
+        self.config = self.config.canonical_headers(canonical);
+        self
+    }
 
-                    let mut response = ResponseBuilder::default();
-                    let mut no_content = false;
+    /// Keeps a copy of the response headers as received (disabled by
+    /// default).
+    ///
+    /// When enabled, the header fields are exposed through
+    /// [`Http11Send::raw_headers`] exactly as sent by the server, e.g.
+    /// for signature verification or debugging: in order, repeated
+    /// fields included, with their original name casing and value
+    /// bytes, before any unfolding or lossy UTF-8 decoding.
+    pub fn capture_raw_headers(mut self, capture: bool) -> Self {
+        self.config = self.config.capture_raw_headers(capture);
+        self
+    }
 
-                    let is_http10 = matches!(parsed.version, Some(0));
-                    response.version = if is_http10 { HTTP_10 } else { HTTP_11 }.into();
+    /// Checks the `Host` header against the request URL (disabled by
+    /// default).
+    ///
+    /// When enabled, a `Host` header whose host or port differs from
+    /// the URL authority, default ports being normalized, fails the
+    /// request with [`Http11SendError::HostMismatch`] before anything
+    /// is sent. Such a mismatch is often a bug, and can be abused to
+    /// route a request to an unexpected virtual host. A request with
+    /// more than one `Host` header, invalid as well (RFC 9112 §3.2),
+    /// fails with [`Http11SendError::DuplicateHost`].
+    pub fn validate_host(mut self, validate: bool) -> Self {
+        self.config = self.config.validate_host(validate);
+        self
+    }
 
-                    if let Some(code) = parsed.code {
-                        no_content = code == 204 || code == 304;
+    /// Checks the echo of `TRACE` requests (disabled by default).
+    ///
+    /// When enabled, the body of a successful response to a `TRACE`
+    /// request, the request as received by the server (RFC 9110
+    /// §9.3.8), is compared to the request sent. A request line or
+    /// header field altered, added or removed on the way fails with
+    /// [`Http11SendError::TraceMismatch`], revealing intermediaries
+    /// such as transparent proxies. Sensitive fields, which servers
+    /// may leave out of the echo, are not compared.
+    pub fn verify_trace(mut self, verify: bool) -> Self {
+        self.config = self.config.verify_trace(verify);
+        self
+    }
+
+    /// Accepts HTTP/0.9 responses (disabled by default).
+    ///
+    /// An HTTP/0.9 server sends the body right away, without status
+    /// line nor headers, then closes the connection. When enabled, a
+    /// response not starting with `HTTP/` is read until close as the
+    /// body of a synthesized `200 OK` response, with version
+    /// [`HTTP_09`]. Otherwise, it fails to parse.
+    pub fn allow_http09(mut self, allow: bool) -> Self {
+        self.config = self.config.allow_http09(allow);
+        self
+    }
+
+    /// Pushes the response body into the given sink as it arrives,
+    /// instead of buffering it (disabled by default).
+    ///
+    /// The sink receives the body decoded from its framing, whether
+    /// delimited by `Content-Length`, chunked or delimited by the
+    /// closure of the connection, segment by segment. The response
+    /// returned then has an empty body. A body with transfer codings
+    /// other than `chunked` is pushed at once, after being decoded.
+    ///
+    /// The body of a redirect is not pushed, but kept in the response
+    /// surfaced by [`Http11SendResult::Redirect`], so that the sink
+    /// only receives the body of the final response when following
+    /// redirects with [`Http11Send::follow`], which keeps the sink.
+    /// The sink is shared by all the coroutines built from the same
+    /// configuration: a sink writing somewhere needs interior
+    /// mutability (e.g. a `Mutex`).
+    ///
+    /// An error returned by the sink stops the coroutine with
+    /// [`Http11SendError::BodySink`].
+    pub fn body_sink<E>(
+        mut self,
+        f: impl Fn(&[u8]) -> Result<(), E> + Send + Sync + 'static,
+    ) -> Self
+    where
+        E: Into<Box<dyn core::error::Error + Send + Sync>>,
+    {
+        self.config = self.config.body_sink(f);
+        self
+    }
+
+    /// Signs the request with the given signer (none by default).
+    ///
+    /// The signer receives the [components](RequestComponents) of the
+    /// request once serialized, and returns the header fields to add,
+    /// typically `Signature-Input` and `Signature` (RFC 9421). They
+    /// are written in order, after all the other fields. The crypto
+    /// is left to the signer.
+    ///
+    /// The signer is kept by [`Http11Send::follow`], so that each
+    /// request of a redirect chain is signed.
+    pub fn sign_with(
+        mut self,
+        f: impl Fn(&RequestComponents) -> Vec<(String, String)> + Send + Sync + 'static,
+    ) -> Self {
+        self.config = self.config.sign_with(f);
+        self
+    }
+
+    /// Fails the coroutine once the given deadline is reached (none by
+    /// default).
+    ///
+    /// The deadline is measured with the given clock, checked on each
+    /// call to [`Http11Send::resume`]: once `clock.now()` reaches
+    /// `deadline`, it returns [`Http11SendError::DeadlineExceeded`].
+    /// The runtime is still responsible for bounding each I/O, as the
+    /// coroutine cannot interrupt one in progress.
+    ///
+    /// The deadline is absolute: it is kept by [`Http11Send::follow`],
+    /// so that it bounds the whole redirect chain.
+    pub fn with_deadline(
+        mut self,
+        clock: impl Clock + Send + Sync + 'static,
+        deadline: Duration,
+    ) -> Self {
+        self.config = self.config.with_deadline(clock, deadline);
+        self
+    }
+
+    /// Renders non-ASCII bytes of the response headers as `\xNN` in
+    /// trace logs (disabled by default).
+    ///
+    /// Headers are otherwise decoded lossily, which replaces invalid
+    /// UTF-8 with `U+FFFD` and hides the bytes actually sent by a
+    /// broken server. Has no effect without the `log` feature.
+    pub fn hex_trace_headers(mut self, hex: bool) -> Self {
+        self.config = self.config.hex_trace_headers(hex);
+        self
+    }
+
+    /// Removes hop-by-hop headers from the response returned
+    /// (disabled by default).
+    ///
+    /// The headers listed in [`HOP_BY_HOP_HEADERS`], along with the
+    /// ones named by `Connection`, only apply to the connection the
+    /// response was received on. They are still used to frame the
+    /// response, then removed, which suits proxies forwarding it.
+    pub fn strip_hop_by_hop(mut self, strip: bool) -> Self {
+        self.config = self.config.strip_hop_by_hop(strip);
+        self
+    }
+
+    /// Rewrites the framing headers of a response received using
+    /// transfer codings (disabled by default).
+    ///
+    /// Once the body is decoded, the `Transfer-Encoding` header no
+    /// longer describes it: it is replaced by a `Content-Length`
+    /// matching the decoded body, so that the response returned is
+    /// self-consistent. Has no effect when the body is pushed into a
+    /// [sink](Http11Send::body_sink).
+    pub fn rewrite_framing_headers(mut self, rewrite: bool) -> Self {
+        self.config = self.config.rewrite_framing_headers(rewrite);
+        self
+    }
+
+    /// Returns the serialized request, if captured.
+    ///
+    /// Only available once the request has been serialized, and when
+    /// [`Http11Send::capture_request_bytes`] is enabled, or when the
+    /// request is a `TRACE` verified by [`Http11Send::verify_trace`].
+    pub fn request_bytes(&self) -> Option<&[u8]> {
+        self.request_bytes.as_deref()
+    }
+
+    /// Takes back the read buffer, for reuse.
+    ///
+    /// Only available once the response headers have been received,
+    /// unless the body is sent using chunked transfer coding: the
+    /// buffer is then handed to the chunked decoder, and dropped with
+    /// it. See [`Http11Send::with_read_buffer`].
+    pub fn take_read_buffer(&mut self) -> Option<Vec<u8>> {
+        self.read_buffer.take()
+    }
+
+    /// Consumes the coroutine, returning the request it was created
+    /// with.
+    ///
+    /// Meant to recover the request after an error, typically an
+    /// unexpected EOF, in order to retry or log it. Returns `None`
+    /// once the request has been handed back by a terminal result
+    /// other than [`Http11SendResult::Err`].
+    pub fn into_request(self) -> Option<HttpRequest> {
+        self.request
+    }
+
+    /// Returns the response header fields as received, if captured.
+    ///
+    /// Only available once the response headers have been parsed, and
+    /// when [`Http11Send::capture_raw_headers`] is enabled.
+    pub fn raw_headers(&self) -> Option<&[(String, Vec<u8>)]> {
+        self.raw_headers.as_deref()
+    }
+
+    /// Signals that the connection was closed abruptly while reading
+    /// a body delimited by the connection closure.
+    ///
+    /// A plain EOF cannot tell a complete body from a truncated one.
+    /// Runtimes able to detect an unclean close, typically a TLS
+    /// connection closed without `close_notify`, report it as an
+    /// error: call this method instead of giving up, then resume the
+    /// coroutine with `None`. The body received so far is then
+    /// returned with [`Http11SendResult::Ok`]'s `truncated` set.
+    ///
+    /// Has no effect on other framings, which detect truncation on
+    /// their own.
+    pub fn close_abruptly(&mut self) {
+        if let State::ReceiveBody { .. } = self.state {
+            self.is_truncated = true;
+        }
+    }
+
+    /// Stops sending the request and starts receiving the response.
+    ///
+    /// Meant to be called after the runtime failed to write the
+    /// request, typically with a broken pipe, in order to read the
+    /// response the server may have sent before closing the
+    /// connection. Resume the coroutine with `None` afterwards.
+    ///
+    /// The connection cannot be reused, so the response is never
+    /// reported as keep-alive. Has no effect once the request has
+    /// been sent.
+    pub fn receive_response(&mut self) {
+        if let State::Send { .. } = self.state {
+            self.is_conn_closed = true;
+            self.state = State::ReceiveHeaders {
+                read: headers_read(self.read_buffer.take()),
+                headers: Vec::new(),
+                scanned: 0,
+            };
+        }
+    }
+
+    /// Advances the coroutine.
+    ///
+    /// Pass `None` on the first call. On subsequent calls, pass the
+    /// [`SocketOutput`] returned by the runtime after processing the
+    /// last emitted [`SocketInput`].
+    pub fn resume(&mut self, mut arg: Option<SocketOutput>) -> Http11SendResult {
+        if let Some(deadline) = &self.config.deadline {
+            if deadline.clock.now() >= deadline.at {
+                return Http11SendResult::Err {
+                    err: Http11SendError::DeadlineExceeded,
+                };
+            }
+        }
+
+        loop {
+            match &mut self.state {
+                State::Serialize => {
+                    let req = self.request.as_ref().unwrap();
+                    let method = req.method.as_str();
+                    info!(method = method, url = req.url.as_str(); "send HTTP/1.1 request");
+                    trace!("HTTP/1.1 request: {req:?}");
+
+                    // a method that is not a token would corrupt the
+                    // request line (RFC 9110 §9.1)
+                    if !is_token(&req.method) {
+                        return Http11SendResult::Err {
+                            err: Http11SendError::InvalidMethod(req.method.clone()),
+                        };
+                    }
+
+                    if self.config.validate_host {
+                        let is_host = |k: &String| k.eq_ignore_ascii_case(HOST);
+                        let hosts: Vec<String> = match &self.ordered_headers {
+                            Some(headers) => headers
+                                .iter()
+                                .filter(|(k, _)| is_host(k))
+                                .map(|(_, v)| String::from_utf8_lossy(v).into_owned())
+                                .collect(),
+                            None => req
+                                .headers
+                                .iter()
+                                .filter(|(k, _)| is_host(k))
+                                .map(|(_, v)| v.clone())
+                                .collect(),
+                        };
+
+                        if hosts.len() > 1 {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::DuplicateHost,
+                            };
+                        }
+
+                        if let Some(host) = hosts.into_iter().next() {
+                            if !is_url_authority(&host, &req.url) {
+                                return Http11SendResult::Err {
+                                    err: Http11SendError::HostMismatch(host),
+                                };
+                            }
+                        }
+                    }
+
+                    // asterisk-form only applies to server-wide
+                    // OPTIONS requests (RFC 9112 §3.2.4), and
+                    // authority-form to CONNECT requests (§3.2.3)
+                    let target = if self.config.asterisk_form && req.method == "OPTIONS" {
+                        String::from("*")
+                    } else if req.method == "CONNECT" {
+                        let host = req.url.host_str().unwrap_or_default();
+                        let port = effective_port(&req.url).unwrap_or(80);
+                        format!("{host}:{port}")
+                    } else {
+                        match req.url.query() {
+                            Some(q) => format!("{}?{q}", req.url.path()),
+                            None => String::from(req.url.path()),
+                        }
+                    };
+
+                    let mut bytes = Vec::new();
+
+                    bytes.extend(req.method.as_bytes());
+                    bytes.push(SP);
+                    bytes.extend(target.as_bytes());
+                    bytes.push(SP);
+                    bytes.extend(HTTP_11.as_bytes());
+                    bytes.extend(CRLF);
+
+                    // trailers can only be sent using chunked transfer
+                    // coding (RFC 9112 §7.1.2)
+                    let chunked = !req.trailers.is_empty()
+                        || self.config.body_framing == RequestFraming::Chunked;
+                    let until_close =
+                        !chunked && self.config.body_framing == RequestFraming::UntilClose;
+
+                    if let Some((name, _)) = req.trailers.iter().find(|(name, _)| {
+                        FORBIDDEN_TRAILERS
+                            .iter()
+                            .any(|forbidden| name.eq_ignore_ascii_case(forbidden))
+                    }) {
+                        return Http11SendResult::Err {
+                            err: Http11SendError::ForbiddenTrailer(name.clone()),
+                        };
+                    }
+
+                    let mut fields: Vec<(String, String)> = Vec::new();
+
+                    for (key, val) in &req.headers {
+                        // skip framing headers, as they are
+                        // automatically generated below
+                        let framing = key.eq_ignore_ascii_case(TRANSFER_ENCODING)
+                            || key.eq_ignore_ascii_case(TRAILER);
+                        let conn = key.eq_ignore_ascii_case(CONNECTION);
+
+                        if key.eq_ignore_ascii_case(CONTENT_LENGTH)
+                            || ((chunked || until_close) && framing)
+                            || (until_close && conn)
+                        {
+                            continue;
+                        }
+
+                        fields.push((key.clone(), val.clone()));
+                    }
+
+                    // methods without defined body semantics do not
+                    // need a Content-Length when empty
+                    let bodyless = req.body.is_empty()
+                        && !self.config.always_send_content_length
+                        && BODYLESS_METHODS.contains(&req.method.as_str());
+
+                    let mut framing_fields: Vec<(String, String)> = Vec::new();
+
+                    if chunked {
+                        let names: Vec<&str> =
+                            req.trailers.iter().map(|(name, _)| name.as_str()).collect();
+
+                        framing_fields.push((TRANSFER_ENCODING.into(), "chunked".into()));
+
+                        if !names.is_empty() {
+                            framing_fields.push((TRAILER.into(), names.join(", ")));
+                        }
+                    } else if until_close {
+                        // the body is delimited by closing the
+                        // connection, which is left to the caller
+                        self.is_conn_closed = true;
+                        framing_fields.push((CONNECTION.into(), "close".into()));
+                    } else if !bodyless {
+                        let len = format!("{}", req.body.len());
+                        framing_fields.push((CONTENT_LENGTH.into(), len));
+                    }
+
+                    let mut lines: Vec<(&str, &[u8])> = match &self.ordered_headers {
+                        Some(headers) => {
+                            // framing fields of the list must match the
+                            // generated ones, so that the body is framed
+                            // once and for all
+                            for (key, val) in headers {
+                                let is_framing = [CONTENT_LENGTH, TRANSFER_ENCODING, TRAILER]
+                                    .iter()
+                                    .any(|name| key.eq_ignore_ascii_case(name))
+                                    || (until_close && key.eq_ignore_ascii_case(CONNECTION));
+
+                                if !is_framing {
+                                    continue;
+                                }
+
+                                let expected = match framing_fields
+                                    .iter()
+                                    .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                                {
+                                    Some((_, v)) => Some(v.clone()),
+                                    // an empty body without Content-Length
+                                    // can still be given a zero one
+                                    None if key.eq_ignore_ascii_case(CONTENT_LENGTH)
+                                        && !chunked
+                                        && !until_close =>
+                                    {
+                                        Some(format!("{}", req.body.len()))
+                                    }
+                                    None => None,
+                                };
+
+                                if !expected.is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(val))
+                                {
+                                    return Http11SendResult::Err {
+                                        err: Http11SendError::FramingFieldMismatch(key.clone()),
+                                    };
+                                }
+                            }
+
+                            // written verbatim, framing fields being only
+                            // generated when missing from the list
+                            let mut lines: Vec<(&str, &[u8])> = headers
+                                .iter()
+                                .map(|(k, v)| (k.as_str(), v.as_slice()))
+                                .collect();
+
+                            for (key, val) in &framing_fields {
+                                if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)) {
+                                    lines.push((key, val.as_bytes()));
+                                }
+                            }
+
+                            lines
+                        }
+                        None => {
+                            fields.append(&mut framing_fields);
+
+                            if self.config.canonical_headers {
+                                // the sort is stable, so that repeated
+                                // fields keep their relative order
+                                for (key, _) in &mut fields {
+                                    key.make_ascii_lowercase();
+                                }
+
+                                fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+                            }
+
+                            fields
+                                .iter()
+                                .map(|(k, v)| (k.as_str(), v.as_bytes()))
+                                .collect()
+                        }
+                    };
+
+                    // the signature fields come last, as they cover
+                    // the fields above
+                    let signature = match &self.config.signer {
+                        Some(signer) => (signer.0)(&RequestComponents {
+                            method: &req.method,
+                            target: &target,
+                            headers: &lines,
+                        }),
+                        None => Vec::new(),
+                    };
+
+                    lines.extend(signature.iter().map(|(k, v)| (k.as_str(), v.as_bytes())));
+
+                    for (key, val) in lines {
+                        push_field(&mut bytes, key, val);
+                    }
+
+                    bytes.extend(CRLF);
+
+                    if chunked {
+                        if !req.body.is_empty() {
+                            let chunk_size = format!("{:x}", req.body.len());
+                            bytes.extend(chunk_size.as_bytes());
+                            bytes.extend(CRLF);
+                            bytes.extend(&req.body);
+                            bytes.extend(CRLF);
+                        }
+
+                        bytes.extend(b"0");
+                        bytes.extend(CRLF);
+
+                        for (key, val) in &req.trailers {
+                            push_field(&mut bytes, key, val.as_bytes());
+                        }
+
+                        bytes.extend(CRLF);
+                    }
+
+                    let is_verified_trace = self.config.verify_trace && req.method == "TRACE";
+
+                    if self.config.capture_request_bytes || is_verified_trace {
+                        let mut captured = bytes.clone();
+
+                        if !chunked {
+                            captured.extend(&req.body);
+                        }
+
+                        self.request_bytes = Some(captured);
+                    }
+
+                    // the body is sent apart, by bounded slices
+                    self.state = State::Send {
+                        write: SocketWrite::new(bytes),
+                        has_body: !chunked && !req.body.is_empty(),
+                        offset: None,
+                        written: 0,
+                    };
+                }
+                State::Send {
+                    write,
+                    has_body,
+                    offset,
+                    written,
+                } => {
+                    match write.resume(arg.take()) {
+                        SocketWriteResult::Ok { mut bytes, n } if n < bytes.len() => {
+                            trace!("resume short HTTP/1.1 request write after {n} bytes");
+                            *written += n;
+
+                            bytes.drain(..n);
+
+                            if let Some(offset) = offset {
+                                *offset += n;
+                            }
+
+                            *write = SocketWrite::new(bytes);
+                            continue;
+                        }
+                        SocketWriteResult::Ok { mut bytes, n } if *has_body => {
+                            let body = &self.request.as_ref().unwrap().body;
+                            *written += n;
+
+                            let start = match offset {
+                                Some(offset) => *offset + n,
+                                None => 0,
+                            };
+
+                            if start < body.len() {
+                                trace!("send HTTP/1.1 request body from byte {start}");
+
+                                // the write buffer is reused for the
+                                // next part of the body
+                                let end = body.len().min(start + MAX_BODY_WRITE_LEN);
+                                bytes.clear();
+                                bytes.extend_from_slice(&body[start..end]);
+
+                                *write = SocketWrite::new(bytes);
+                                *offset = Some(start);
+                                continue;
+                            }
+                        }
+                        SocketWriteResult::Ok { .. } => (),
+                        SocketWriteResult::Err { err } => {
+                            return Http11SendResult::Err { err: err.into() };
+                        }
+                        SocketWriteResult::Io { input } => {
+                            return Http11SendResult::Io { input };
+                        }
+                        SocketWriteResult::Eof if self.config.early_response => {
+                            trace!("server stopped accepting the request, receive response");
+                            self.receive_response();
+                            continue;
+                        }
+                        SocketWriteResult::Eof => {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::UnexpectedEof {
+                                    phase: Http11SendPhase::Sending,
+                                    bytes_so_far: *written,
+                                },
+                            };
+                        }
+                    };
+
+                    trace!("resume after sending HTTP/1.1 request");
+
+                    self.state = State::ReceiveHeaders {
+                        read: headers_read(self.read_buffer.take()),
+                        headers: Vec::new(),
+                        scanned: 0,
+                    };
+                }
+                State::ReceiveHeaders {
+                    read,
+                    headers,
+                    scanned,
+                } => {
+                    let (buf, n) = match read.resume(arg.take()) {
+                        SocketReadResult::Ok { buf, n } => (buf, n),
+                        SocketReadResult::Err { err } => {
+                            return Http11SendResult::Err { err: err.into() };
+                        }
+                        SocketReadResult::Io { input } => {
+                            return Http11SendResult::Io { input };
+                        }
+                        SocketReadResult::Eof => {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::UnexpectedEof {
+                                    phase: Http11SendPhase::ReceivingHeaders,
+                                    bytes_so_far: headers.len(),
+                                },
+                            };
+                        }
+                    };
+
+                    trace!("resume after receiving partial HTTP/1.1 response headers");
+
+                    headers.extend_from_slice(&buf[..n]);
+
+                    // an HTTP/0.9 response is the body alone, as soon
+                    // as it does not start like a status line
+                    let prefix = &headers[..headers.len().min(5)];
+
+                    if self.config.allow_http09 && !b"HTTP/".starts_with(prefix) {
+                        trace!("received HTTP/0.9 response, read body until close");
+                        read.replace(buf);
+
+                        let response = ResponseBuilder {
+                            status: Some(StatusCode(200)),
+                            reason: "OK".into(),
+                            version: HTTP_09.into(),
+                            ..Default::default()
+                        };
+
+                        self.is_conn_closed = true;
+                        self.body_sink = self.config.body_sink.clone();
+                        self.state = State::ReceiveBody {
+                            read: mem::take(read),
+                            response,
+                            body: mem::take(headers),
+                        };
+                        continue;
+                    }
+
+                    if !has_headers_end(headers, *scanned) {
+                        trace!("received incomplete HTTP/1.1 response headers, need more bytes");
+                        *scanned = headers.len();
+                        read.replace(buf);
+                        continue;
+                    }
+
+                    let mut parsed = [httparse::EMPTY_HEADER; 64];
+                    let mut parsed = httparse::Response::new(&mut parsed);
+
+                    // folded lines are accepted by the parser, then
+                    // either rejected or unfolded below
+                    let mut config = httparse::ParserConfig::default();
+                    config.allow_obsolete_multiline_headers_in_responses(true);
+
+                    let n = match config.parse_response(&mut parsed, headers) {
+                        Ok(httparse::Status::Complete(n)) => n,
+                        Ok(httparse::Status::Partial) => {
+                            trace!(
+                                "received incomplete HTTP/1.1 response headers, need more bytes"
+                            );
+                            read.replace(buf);
+                            continue;
+                        }
+                        Err(err) => {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::ParseResponseHeaders(err),
+                            };
+                        }
+                    };
+
+                    #[cfg(feature = "log")]
+                    if log::log_enabled!(log::Level::Trace) {
+                        let h = if self.config.hex_trace_headers {
+                            escape_non_ascii(&headers[..n])
+                        } else {
+                            String::from_utf8_lossy(&headers[..n]).into_owned()
+                        };
+                        trace!("HTTP/1.1 response headers:\n{h}");
+                    }
+
+                    let mut response = ResponseBuilder::default();
+                    let mut no_content = false;
+
+                    let is_http10 = matches!(parsed.version, Some(0));
+                    response.version = if is_http10 { HTTP_10 } else { HTTP_11 }.into();
+
+                    if let Some(code) = parsed.code {
+                        no_content = code == 204 || code == 304;
                         response.status = Some(StatusCode(code));
                     }
 
+                    if let Some(reason) = parsed.reason {
+                        response.reason = reason.into();
+                    }
+
+                    if self.config.capture_raw_headers {
+                        let raw = parsed.headers.iter();
+                        let raw = raw.map(|h| (String::from(h.name), h.value.to_vec()));
+                        self.raw_headers = Some(raw.collect());
+                    }
+
                     for header in parsed.headers {
-                        response.header(header.name, header.value);
+                        // obsolete line folding (RFC 9112 §5.2)
+                        if memchr::memchr(LF, header.value).is_none() {
+                            response.header(header.name, header.value);
+                        } else if self.config.strict {
+                            let name = header.name.into();
+                            return Http11SendResult::Err {
+                                err: Http11SendError::ObsoleteLineFolding(name),
+                            };
+                        } else {
+                            response.header(header.name, &unfold(header.value));
+                        }
                     }
 
                     let body: Vec<u8> = headers.drain(n..).collect();
 
-                    if let Some(conn) = response.get_header(CONNECTION) {
-                        self.is_conn_closed = conn.eq_ignore_ascii_case("close");
-                    } else {
-                        // HTTP/1.0 closes connections by default;
-                        // HTTP/1.1 keeps them alive.
-                        self.is_conn_closed = is_http10;
+                    // a request body delimited by closing the
+                    // connection already prevents its reuse
+                    self.is_conn_closed |= !keep_alive(&response.version, &response.headers);
+
+                    // a successful CONNECT switches the connection to
+                    // tunnel mode, without response body (RFC 9110
+                    // §9.3.6)
+                    let req = self.request.as_ref().unwrap();
+                    let success = response.status.is_some_and(|s| s.is_success());
+
+                    if req.method == "CONNECT" && success {
+                        self.read_buffer = Some(buf);
+                        break Http11SendResult::Tunnel {
+                            request: self.request.take().unwrap(),
+                            response: response.build(vec![]),
+                            buf: body,
+                        };
+                    }
+
+                    // the body of a redirect is kept in the response
+                    // surfaced to the caller, rather than pushed into
+                    // the sink meant for the final response
+                    let is_redirect = response.status.is_some_and(|s| s.is_redirection())
+                        && response.get_header(LOCATION).is_some();
+
+                    if !is_redirect {
+                        self.body_sink = self.config.body_sink.clone();
                     }
 
                     if no_content {
+                        self.read_buffer = Some(buf);
+                        let mut response = response.build(vec![]);
+
+                        if self.config.strip_hop_by_hop {
+                            strip_hop_by_hop(&mut response);
+                        }
+
+                        let status = *response.status;
+                        info!(status = status, body_len = 0; "receive HTTP/1.1 response");
                         break Http11SendResult::Ok {
                             request: self.request.take().unwrap(),
-                            response: response.build(vec![]),
+                            not_modified: *response.status == 304,
+                            truncated: false,
+                            response,
                             keep_alive: !self.is_conn_closed,
+                            framing: BodyFraming::Empty,
                         };
                     }
 
                     // Chunked transfer coding is HTTP/1.1 only (RFC
                     // 9112 §7.1).
-                    if !is_http10 {
-                        if let Some(enc) = response.get_header(TRANSFER_ENCODING) {
-                            if enc.eq_ignore_ascii_case("chunked") {
-                                let capacity = buf.capacity();
-                                let mut read = SocketRead::with_capacity(capacity);
-                                read.replace(buf);
+                    let codings = if is_http10 {
+                        Vec::new()
+                    } else {
+                        transfer_codings(response.get_headers(TRANSFER_ENCODING))
+                    };
+
+                    // Transfer codings are listed in the order they
+                    // were applied, chunked must be the final one
+                    // (RFC 9112 §6.1).
+                    if let Some((last, codings)) = codings.split_last() {
+                        if last != "chunked" {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::ChunkedNotLast,
+                            };
+                        }
 
-                                let mut read = HttpChunksRead::new(read);
-                                read.extend(body);
+                        let capacity = buf.capacity();
+                        let mut read = SocketRead::with_capacity(capacity);
+                        read.replace(buf);
 
-                                self.state = State::ReceiveChunkedBody { read, response };
-                                continue;
+                        // chunks are pushed into the sink as soon as
+                        // they are decoded
+                        let streaming = self.body_sink.is_some();
+                        let mut read = HttpChunksRead::new(read)
+                            .streaming(streaming)
+                            .max_chunk_size(self.config.max_chunk_size);
+                        read.extend(body);
+
+                        let [coding, ..] = codings else {
+                            self.state = State::ReceiveChunkedBody { read, response };
+                            continue;
+                        };
+
+                        #[cfg(feature = "compression")]
+                        if let ([_], Some(coding)) = (codings, ContentCoding::from_name(coding)) {
+                            let mut read = ContentDecode::chunked(coding, read);
+
+                            if let Some(ratio) = self.config.max_decode_ratio {
+                                read = read.max_ratio(ratio);
+                            }
+
+                            if let Some(len) = self.config.max_decoded_len {
+                                read = read.max_decoded_len(len);
                             }
+
+                            self.state = State::ReceiveDecodedChunkedBody { read, response };
+                            continue;
                         }
+
+                        return Http11SendResult::Err {
+                            err: Http11SendError::UnsupportedTransferCoding(coding.clone()),
+                        };
                     }
 
-                    if let Some(len) = response.get_header(CONTENT_LENGTH) {
-                        if let Ok(len) = usize::from_str_radix(len.trim(), 10) {
-                            let mut read = SocketReadExact::new(len);
-                            read.extend(body);
-                            self.state = State::ReceiveLengthedBody { read, response };
-                            continue;
+                    let Ok(len) = content_length(response.get_headers(CONTENT_LENGTH)) else {
+                        return Http11SendResult::Err {
+                            err: Http11SendError::AmbiguousBodyFraming,
+                        };
+                    };
+
+                    if let Some(len) = len {
+                        // the body is buffered, so its length must
+                        // fit in memory (e.g. on 32-bit targets)
+                        let Ok(len) = usize::try_from(len) else {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::ContentLengthTooLarge(len),
+                            };
+                        };
+
+                        // the header read buffer is reused, unless the
+                        // body has already been received
+                        let mut read = SocketRead::with_capacity(0);
+
+                        let remaining = len.saturating_sub(body.len());
+                        let capacity = self.read_buffer_capacity;
+
+                        match body_read_capacity(&self.config, capacity, remaining) {
+                            0 => self.read_buffer = Some(buf),
+                            capacity => {
+                                let mut buf = buf;
+                                buf.resize(capacity, 0);
+                                read.replace(buf);
+                            }
                         }
+
+                        self.state = State::ReceiveLengthedBody {
+                            read,
+                            response,
+                            body,
+                            len,
+                            sunk: 0,
+                        };
+                        continue;
+                    }
+
+                    if self.config.strict && !self.is_conn_closed {
+                        return Http11SendResult::Err {
+                            err: Http11SendError::UnboundedBodyOnKeepAlive,
+                        };
                     }
 
-                    let mut read = SocketReadToEnd::new();
-                    read.extend(body);
-                    self.state = State::ReceiveBody { read, response };
+                    self.read_buffer = Some(buf);
+
+                    self.state = State::ReceiveBody {
+                        read: SocketRead::default(),
+                        response,
+                        body,
+                    };
                 }
                 State::ReceiveChunkedBody { read, response } => {
                     let body = match read.resume(arg.take()) {
                         HttpChunksReadResult::Ok { body } => body,
+                        HttpChunksReadResult::Chunk { mut chunk } => {
+                            // streaming is enabled for the sink only
+                            if let Err(err) = sink_body(&self.body_sink, &mut chunk) {
+                                return Http11SendResult::Err { err };
+                            }
+
+                            continue;
+                        }
                         HttpChunksReadResult::Err { err } => {
                             return Http11SendResult::Err { err: err.into() };
                         }
@@ -406,80 +1862,449 @@ impl Http11Send {
                         }
                     };
 
-                    break finish(
-                        self.request.take().unwrap(),
-                        mem::take(response).build(body),
-                        !self.is_conn_closed,
-                    );
+                    let mut response = mem::take(response).build(body);
+                    push_trailers(&mut response, read.trailers(), self.config.merge_trailers);
+
+                    if self.config.rewrite_framing_headers && self.body_sink.is_none() {
+                        rewrite_framing_headers(&mut response);
+                    }
+
+                    break self.finish(response, BodyFraming::Chunked);
                 }
-                State::ReceiveLengthedBody { read, response } => {
-                    let body = match read.resume(arg.take()) {
-                        SocketReadExactResult::Ok { buf } => buf,
-                        SocketReadExactResult::Err { err } => {
+                #[cfg(feature = "compression")]
+                State::ReceiveDecodedChunkedBody { read, response } => {
+                    let mut body = match read.resume(arg.take()) {
+                        ContentDecodeResult::Ok { body } => body,
+                        ContentDecodeResult::Err { err } => {
                             return Http11SendResult::Err { err: err.into() };
                         }
-                        SocketReadExactResult::Io { input } => {
+                        ContentDecodeResult::Io { input } => {
                             return Http11SendResult::Io { input };
                         }
                     };
 
-                    break finish(
-                        self.request.take().unwrap(),
-                        mem::take(response).build(body),
-                        !self.is_conn_closed,
-                    );
+                    if let Err(err) = sink_body(&self.body_sink, &mut body) {
+                        return Http11SendResult::Err { err };
+                    }
+
+                    let mut response = mem::take(response).build(body);
+                    push_trailers(&mut response, read.trailers(), self.config.merge_trailers);
+
+                    if self.config.rewrite_framing_headers && self.body_sink.is_none() {
+                        rewrite_framing_headers(&mut response);
+                    }
+
+                    break self.finish(response, BodyFraming::Chunked);
                 }
-                State::ReceiveBody { read, response } => {
-                    let body = match read.resume(arg.take()) {
-                        SocketReadToEndResult::Ok { buf } => buf,
-                        SocketReadToEndResult::Err { err } => {
+                State::ReceiveLengthedBody {
+                    read,
+                    response,
+                    body,
+                    len,
+                    sunk,
+                } => {
+                    // bytes past the body are never part of it
+                    body.truncate(*len - *sunk);
+
+                    if self.body_sink.is_some() {
+                        *sunk += body.len();
+
+                        if let Err(err) = sink_body(&self.body_sink, body) {
+                            return Http11SendResult::Err { err };
+                        }
+                    }
+
+                    if *sunk + body.len() >= *len {
+                        let body = mem::take(body);
+                        let framing = BodyFraming::ContentLength(*len);
+                        let response = mem::take(response).build(body);
+                        break self.finish(response, framing);
+                    }
+
+                    let (mut buf, n) = match read.resume(arg.take()) {
+                        SocketReadResult::Ok { buf, n } => (buf, n),
+                        SocketReadResult::Err { err } => {
                             return Http11SendResult::Err { err: err.into() };
                         }
-                        SocketReadToEndResult::Io { input } => {
+                        SocketReadResult::Io { input } => {
                             return Http11SendResult::Io { input };
                         }
+                        SocketReadResult::Eof => {
+                            return Http11SendResult::Err {
+                                err: Http11SendError::UnexpectedEof {
+                                    phase: Http11SendPhase::ReceivingBody,
+                                    bytes_so_far: *sunk + body.len(),
+                                },
+                            };
+                        }
                     };
 
-                    break finish(
-                        self.request.take().unwrap(),
-                        mem::take(response).build(body),
-                        !self.is_conn_closed,
-                    );
+                    trace!("resume after receiving partial HTTP/1.1 response body");
+                    body.extend_from_slice(&buf[..n]);
+
+                    // size the next read toward the remaining length,
+                    // so that no byte past the body is consumed
+                    let remaining = len.saturating_sub(*sunk + body.len());
+                    let capacity = self.read_buffer_capacity;
+
+                    match body_read_capacity(&self.config, capacity, remaining) {
+                        0 => self.read_buffer = Some(buf),
+                        capacity => {
+                            buf.resize(capacity, 0);
+                            read.replace(buf);
+                        }
+                    }
+                }
+                State::ReceiveBody {
+                    read,
+                    response,
+                    body,
+                } => {
+                    if !self.is_truncated {
+                        match read.resume(arg.take()) {
+                            SocketReadResult::Ok { buf, n } => {
+                                trace!("resume after receiving partial HTTP/1.1 response body");
+                                body.extend_from_slice(&buf[..n]);
+                                read.replace(buf);
+
+                                if let Err(err) = sink_body(&self.body_sink, body) {
+                                    return Http11SendResult::Err { err };
+                                }
+
+                                continue;
+                            }
+                            SocketReadResult::Err { err } => {
+                                return Http11SendResult::Err { err: err.into() };
+                            }
+                            SocketReadResult::Io { input } => {
+                                return Http11SendResult::Io { input };
+                            }
+                            SocketReadResult::Eof => (),
+                        }
+                    }
+
+                    // bytes received along with the headers
+                    if let Err(err) = sink_body(&self.body_sink, body) {
+                        return Http11SendResult::Err { err };
+                    }
+
+                    let body = mem::take(body);
+                    let response = mem::take(response).build(body);
+                    break self.finish(response, BodyFraming::UntilClose);
                 }
             }
         }
     }
-}
 
-/// Converts a completed request/response pair into the appropriate
-/// [`Http11SendResult`].
-///
-/// If the response is a 3xx with a parseable `Location` header, emits
-/// [`Http11SendResult::Redirect`]; otherwise emits
-/// [`Http11SendResult::Ok`].
-fn finish(request: HttpRequest, response: HttpResponse, keep_alive: bool) -> Http11SendResult {
-    if response.status.is_redirection() {
-        if let Some(location) = response.header(LOCATION) {
-            if let Ok(url) = request.url.join(location) {
-                let same_scheme = request.url.scheme() == url.scheme();
-                let same_host =
-                    request.url.host() == url.host() && request.url.port() == url.port();
-                let same_origin = same_scheme && same_host;
-
-                return Http11SendResult::Redirect {
-                    url,
-                    request,
-                    response,
-                    keep_alive,
-                    same_origin,
+    /// Converts the completed response into the appropriate
+    /// [`Http11SendResult`].
+    ///
+    /// If the response is a 3xx with a parseable `Location` header,
+    /// emits [`Http11SendResult::Redirect`], unless the redirect
+    /// targets a scheme other than `http` and `https`, is a forbidden
+    /// `https` downgrade or the redirect policy decides otherwise;
+    /// otherwise emits [`Http11SendResult::Ok`].
+    fn finish(&mut self, mut response: HttpResponse, framing: BodyFraming) -> Http11SendResult {
+        let request = self.request.take().unwrap();
+        let keep_alive = !self.is_conn_closed;
+
+        if self.config.strip_hop_by_hop {
+            strip_hop_by_hop(&mut response);
+        }
+
+        let status = *response.status;
+        let body_len = response.body.len();
+        info!(status = status, body_len = body_len; "receive HTTP/1.1 response");
+
+        if response.status.is_redirection() {
+            if let Some(url) = response.location(&request.url) {
+                // a redirect to another scheme, like javascript: or
+                // data:, cannot be followed with HTTP
+                if !matches!(url.scheme(), "http" | "https") {
+                    let err = Http11SendError::UnsupportedRedirectScheme(url.scheme().into());
+                    self.request = Some(request);
+                    return Http11SendResult::Err { err };
+                }
+
+                if self.config.forbid_https_downgrade
+                    && request.url.scheme() == "https"
+                    && url.scheme() == "http"
+                {
+                    let err = Http11SendError::InsecureRedirect(url);
+                    self.request = Some(request);
+                    return Http11SendResult::Err { err };
+                }
+
+                let action = match &self.config.redirect_policy {
+                    Some(policy) => policy.action(&request, &response, &url),
+                    None => RedirectAction::Follow,
                 };
+
+                match action {
+                    RedirectAction::Follow => {
+                        let same_scheme = request.url.scheme() == url.scheme();
+                        let same_host =
+                            request.url.host() == url.host() && request.url.port() == url.port();
+                        let same_origin = same_scheme && same_host;
+
+                        return Http11SendResult::Redirect {
+                            url,
+                            request,
+                            response,
+                            keep_alive,
+                            same_origin,
+                        };
+                    }
+                    RedirectAction::Error => {
+                        let err = Http11SendError::RedirectRefused(url);
+                        self.request = Some(request);
+                        return Http11SendResult::Err { err };
+                    }
+                    RedirectAction::Stop => (),
+                }
             }
         }
+
+        if self.config.verify_trace && request.method == "TRACE" && response.status.is_success() {
+            let sent = self.request_bytes.as_deref().unwrap_or_default();
+
+            if !is_trace_echo(sent, &response.body) {
+                let err = Http11SendError::TraceMismatch;
+                self.request = Some(request);
+                return Http11SendResult::Err { err };
+            }
+        }
+
+        // a redirect not surfaced ends the chain, its body is pushed
+        // at once
+        if let Err(err) = sink_body(&self.config.body_sink, &mut response.body) {
+            self.request = Some(request);
+            return Http11SendResult::Err { err };
+        }
+
+        Http11SendResult::Ok {
+            not_modified: *response.status == 304,
+            truncated: self.is_truncated,
+            request,
+            response,
+            keep_alive,
+            framing,
+        }
     }
+}
+
+/// Removes the hop-by-hop headers from the given response, including
+/// the ones named by `Connection` (RFC 9110 §7.6.1).
+fn strip_hop_by_hop(response: &mut HttpResponse) {
+    let connection: Vec<String> = response
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(CONNECTION))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| String::from(name.trim()))
+        .collect();
+
+    response.headers.retain(|(name, _)| {
+        let mut hop_by_hop = HOP_BY_HOP_HEADERS.iter().copied();
+        let mut listed = connection.iter().map(String::as_str);
+        let is_name = |hop: &str| hop.eq_ignore_ascii_case(name);
+        !hop_by_hop.any(is_name) && !listed.any(is_name)
+    });
+}
 
-    Http11SendResult::Ok {
-        request,
-        response,
-        keep_alive,
+/// Pushes the given body bytes into the sink, if any, leaving them
+/// empty.
+fn sink_body(sink: &Option<BodySink>, body: &mut Vec<u8>) -> Result<(), Http11SendError> {
+    let Some(sink) = sink else {
+        return Ok(());
+    };
+
+    if !body.is_empty() {
+        (sink.0)(body)?;
+        body.clear();
+    }
+
+    Ok(())
+}
+
+/// Creates the read sub-coroutine receiving response headers, using
+/// the given read buffer up to its capacity if any.
+fn headers_read(buf: Option<Vec<u8>>) -> SocketRead {
+    let Some(mut buf) = buf.filter(|buf| buf.capacity() > 0) else {
+        return SocketRead::default();
+    };
+
+    buf.resize(buf.capacity(), 0);
+    let mut read = SocketRead::with_capacity(0);
+    read.replace(buf);
+    read
+}
+
+/// Returns the capacity of the next read of a body delimited by
+/// `Content-Length`, sized toward the `remaining` length, or 0 when
+/// the body has been entirely received.
+fn body_read_capacity(
+    config: &Http11SendBuilder,
+    read_buffer_capacity: Option<usize>,
+    remaining: usize,
+) -> usize {
+    if remaining == 0 {
+        return 0;
+    }
+
+    let capacity = remaining.min(config.max_read_buffer_capacity);
+    let capacity = capacity.min(read_buffer_capacity.unwrap_or(usize::MAX));
+    capacity.max(1)
+}
+
+/// Renders the given bytes as text, non-ASCII bytes escaped as
+/// `\xNN`.
+#[cfg(feature = "log")]
+fn escape_non_ascii(bytes: &[u8]) -> String {
+    use core::fmt::Write;
+
+    let mut escaped = String::with_capacity(bytes.len());
+
+    for &b in bytes {
+        if b.is_ascii() {
+            escaped.push(char::from(b));
+        } else {
+            let _ = write!(escaped, "\\x{b:02X}");
+        }
+    }
+
+    escaped
+}
+
+/// Returns whether the given `TRACE` echo matches the request sent.
+///
+/// Request lines must be identical, and header fields the same
+/// regardless of their order, sensitive ones excepted.
+fn is_trace_echo(sent: &[u8], echo: &[u8]) -> bool {
+    fn fields<'a>(headers: &[httparse::Header<'a>]) -> Vec<(String, &'a [u8])> {
+        let mut fields: Vec<_> = headers
+            .iter()
+            .map(|h| (h.name.to_ascii_lowercase(), h.value.trim_ascii()))
+            .filter(|(name, _)| !SENSITIVE_HEADERS.contains(&name.as_str()))
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    let mut sent_headers = [httparse::EMPTY_HEADER; 64];
+    let mut sent_req = httparse::Request::new(&mut sent_headers);
+    let mut echo_headers = [httparse::EMPTY_HEADER; 64];
+    let mut echo_req = httparse::Request::new(&mut echo_headers);
+
+    let Ok(httparse::Status::Complete(_)) = sent_req.parse(sent) else {
+        return false;
+    };
+
+    let Ok(httparse::Status::Complete(_)) = echo_req.parse(echo) else {
+        return false;
+    };
+
+    sent_req.method == echo_req.method
+        && sent_req.path == echo_req.path
+        && sent_req.version == echo_req.version
+        && fields(sent_req.headers) == fields(echo_req.headers)
+}
+
+/// Returns whether the given `Host` value designates the authority of
+/// the given URL, a missing port standing for the scheme default one.
+fn is_url_authority(host: &str, url: &Url) -> bool {
+    let host = host.trim();
+
+    // IPv6 literals are enclosed in brackets (RFC 3986 §3.2.2)
+    let (name, port) = match host.rfind(':') {
+        Some(i) if !host[i..].contains(']') => (&host[..i], Some(&host[i + 1..])),
+        _ => (host, None),
+    };
+
+    let port = match port {
+        Some(port) => port.parse().ok(),
+        None => effective_port(url),
+    };
+
+    url.host_str()
+        .is_some_and(|url_host| url_host.eq_ignore_ascii_case(name))
+        && port.is_some()
+        && port == effective_port(url)
+}
+
+/// Returns the port of the given URL, or the default port of its
+/// scheme.
+fn effective_port(url: &Url) -> Option<u16> {
+    url.port().or_else(|| default_port(url.scheme()))
+}
+
+/// Returns whether the given response head contains the empty line
+/// terminating the header section, looking only at the bytes received
+/// after the `scanned` offset.
+///
+/// Bare LF line endings are accepted as well, as httparse does.
+fn has_headers_end(headers: &[u8], scanned: usize) -> bool {
+    // the terminator may straddle the previous scan boundary
+    let start = scanned.saturating_sub(CRLF_CRLF.len() - 1);
+
+    memchr::memchr_iter(LF, &headers[start..]).any(|i| {
+        let rest = &headers[start + i + 1..];
+        rest.starts_with(&[LF]) || rest.starts_with(&CRLF)
+    })
+}
+
+/// Unfolds a header value folded over several lines, replacing each
+/// line break and the whitespace around it with a single space (RFC
+/// 9112 §5.2).
+fn unfold(value: &[u8]) -> Vec<u8> {
+    let mut unfolded = Vec::with_capacity(value.len());
+
+    for line in value.split(|&b| b == LF).map(<[u8]>::trim_ascii) {
+        if line.is_empty() {
+            continue;
+        }
+
+        if !unfolded.is_empty() {
+            unfolded.push(SP);
+        }
+
+        unfolded.extend_from_slice(line);
+    }
+
+    unfolded
+}
+
+/// Serializes the given field line into the given bytes.
+fn push_field(bytes: &mut Vec<u8>, key: &str, val: &[u8]) {
+    bytes.extend(key.as_bytes());
+    bytes.extend(b": ");
+    bytes.extend(val);
+    bytes.extend(CRLF);
+}
+
+/// Replaces the `Transfer-Encoding` of the given response with the
+/// `Content-Length` of its decoded body.
+fn rewrite_framing_headers(response: &mut HttpResponse) {
+    response.headers.retain(|(name, _)| {
+        !name.eq_ignore_ascii_case(TRANSFER_ENCODING) && !name.eq_ignore_ascii_case(CONTENT_LENGTH)
+    });
+
+    let len = format!("{}", response.body.len());
+    response.headers.push((CONTENT_LENGTH.into(), len));
+}
+
+/// Adds the trailer fields received after the last chunk to the
+/// response, either apart or merged into its headers.
+fn push_trailers(response: &mut HttpResponse, trailers: &[(String, String)], merge: bool) {
+    for (name, value) in trailers {
+        let forbidden = FORBIDDEN_TRAILERS
+            .iter()
+            .any(|forbidden| name.eq_ignore_ascii_case(forbidden));
+
+        if !merge {
+            response.trailers.push((name.clone(), value.clone()));
+        } else if !forbidden {
+            response.headers.push((name.clone(), value.clone()));
+        }
     }
 }
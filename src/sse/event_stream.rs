@@ -0,0 +1,302 @@
+//! I/O-free coroutine to parse a `text/event-stream` response body
+//! into server-sent events (WHATWG HTML §9.2.6).
+//!
+//! The coroutine reads the body either as-is from the socket, or
+//! through a [`HttpChunksRead`] in streaming mode when the response
+//! carries `Transfer-Encoding: chunked`. Events are emitted one at a
+//! time, as soon as their terminating blank line is received.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::mem;
+
+use io_socket::{
+    coroutines::read::{SocketRead, SocketReadError, SocketReadResult},
+    io::{SocketInput, SocketOutput},
+};
+use thiserror::Error;
+
+use crate::rfc9112::chunk::{HttpChunksRead, HttpChunksReadError, HttpChunksReadResult};
+
+const BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+
+/// Default event type, used when no `event` field is given.
+pub const DEFAULT_EVENT_TYPE: &str = "message";
+
+/// Errors that can occur during the coroutine progression.
+#[derive(Debug, Error)]
+pub enum EventStreamReadError {
+    #[error(transparent)]
+    SocketRead(#[from] SocketReadError),
+    #[error(transparent)]
+    HttpChunksRead(#[from] HttpChunksReadError),
+}
+
+/// Result returned by [`EventStreamRead::resume`].
+#[derive(Debug)]
+pub enum EventStreamReadResult {
+    /// An event has been dispatched.
+    ///
+    /// Resume the coroutine with `None` to read the next one.
+    Event { event: SseEvent },
+    /// The stream has been closed by the server.
+    ///
+    /// A trailing event not terminated by a blank line is discarded,
+    /// as required by the specification.
+    Ok,
+    /// The coroutine needs a socket I/O to be performed.
+    Io { input: SocketInput },
+    /// The coroutine encountered an error.
+    Err { err: EventStreamReadError },
+}
+
+/// A server-sent event.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SseEvent {
+    /// The event type, [`DEFAULT_EVENT_TYPE`] by default.
+    pub event: String,
+    /// The event data, `data` fields being joined by `\n`.
+    pub data: String,
+    /// The last event ID seen on the stream, if any.
+    ///
+    /// Unlike other fields, the ID persists across events until the
+    /// server sends a new one.
+    pub id: Option<String>,
+    /// The last reconnection time (in milliseconds) sent by the
+    /// server, if any.
+    pub retry: Option<u64>,
+}
+
+/// Source of the event stream bytes.
+#[derive(Debug)]
+enum Source {
+    /// The body is read as-is from the socket.
+    Identity(SocketRead),
+    /// The body is decoded from chunked transfer coding.
+    Chunked(HttpChunksRead),
+}
+
+/// I/O-free coroutine to read server-sent events from a response
+/// body.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use io_http::sse::event_stream::{EventStreamRead, EventStreamReadResult};
+/// use io_socket::{coroutines::read::SocketRead, runtimes::std_stream::handle};
+///
+/// let mut read = EventStreamRead::new(SocketRead::default());
+/// let mut arg = None;
+///
+/// loop {
+///     match read.resume(arg.take()) {
+///         EventStreamReadResult::Event { event } => println!("{}: {}", event.event, event.data),
+///         EventStreamReadResult::Ok => break,
+///         EventStreamReadResult::Err { err } => panic!("{err}"),
+///         EventStreamReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventStreamRead {
+    source: Source,
+    buffer: Vec<u8>,
+    bom_checked: bool,
+    eof: bool,
+    event: String,
+    data: String,
+    id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl EventStreamRead {
+    /// Creates a new coroutine reading the event stream as-is from
+    /// the given [`SocketRead`] sub-coroutine.
+    pub fn new(read: SocketRead) -> Self {
+        Self::with_source(Source::Identity(read))
+    }
+
+    /// Creates a new coroutine reading the event stream through the
+    /// given chunked transfer coding decoder.
+    ///
+    /// The decoder is switched to streaming mode, so that events are
+    /// emitted as soon as their chunk is received.
+    pub fn chunked(read: HttpChunksRead) -> Self {
+        Self::with_source(Source::Chunked(read.streaming(true)))
+    }
+
+    fn with_source(source: Source) -> Self {
+        Self {
+            source,
+            buffer: Vec::new(),
+            bom_checked: false,
+            eof: false,
+            event: String::new(),
+            data: String::new(),
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Extends the inner buffer with already-decoded body bytes.
+    pub fn extend(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        self.buffer.extend(bytes);
+    }
+
+    /// Advances the coroutine.
+    ///
+    /// Pass `None` on the first call. On subsequent calls, pass the
+    /// [`SocketOutput`] returned by the runtime after processing the
+    /// last emitted [`SocketInput`].
+    pub fn resume(&mut self, mut arg: Option<SocketOutput>) -> EventStreamReadResult {
+        loop {
+            if !self.bom_checked {
+                // the BOM can only be detected once enough bytes
+                // have been received
+                if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) && !self.eof {
+                    if let Err(result) = self.read(arg.take()) {
+                        return result;
+                    }
+                    continue;
+                }
+
+                if self.buffer.starts_with(&BOM) {
+                    self.buffer.drain(..BOM.len());
+                }
+
+                self.bom_checked = true;
+            }
+
+            if let Some(line) = self.next_line() {
+                if let Some(event) = self.process_line(&line) {
+                    return EventStreamReadResult::Event { event };
+                }
+                continue;
+            }
+
+            if self.eof {
+                return EventStreamReadResult::Ok;
+            }
+
+            if let Err(result) = self.read(arg.take()) {
+                return result;
+            }
+        }
+    }
+
+    /// Reads more bytes from the source into the inner buffer.
+    ///
+    /// Returns the result to emit when the coroutine cannot go
+    /// further without I/O.
+    fn read(&mut self, arg: Option<SocketOutput>) -> Result<(), EventStreamReadResult> {
+        match &mut self.source {
+            Source::Identity(read) => match read.resume(arg) {
+                SocketReadResult::Ok { buf, n } => {
+                    self.buffer.extend_from_slice(&buf[..n]);
+                    read.replace(buf);
+                }
+                SocketReadResult::Eof => self.eof = true,
+                SocketReadResult::Io { input } => {
+                    return Err(EventStreamReadResult::Io { input });
+                }
+                SocketReadResult::Err { err } => {
+                    return Err(EventStreamReadResult::Err { err: err.into() });
+                }
+            },
+            Source::Chunked(read) => match read.resume(arg) {
+                HttpChunksReadResult::Chunk { chunk } => self.buffer.extend(chunk),
+                HttpChunksReadResult::Ok { .. } => self.eof = true,
+                HttpChunksReadResult::Io { input } => {
+                    return Err(EventStreamReadResult::Io { input });
+                }
+                HttpChunksReadResult::Err { err } => {
+                    return Err(EventStreamReadResult::Err { err: err.into() });
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Extracts the next line from the inner buffer, if complete.
+    ///
+    /// Lines can be terminated by CRLF, LF or CR.
+    fn next_line(&mut self) -> Option<String> {
+        let end = memchr::memchr2(CR, LF, &self.buffer)?;
+
+        let len = match self.buffer[end] {
+            // a trailing CR may be followed by a LF not received yet
+            CR if end + 1 == self.buffer.len() && !self.eof => return None,
+            CR if self.buffer.get(end + 1) == Some(&LF) => 2,
+            _ => 1,
+        };
+
+        let line = String::from_utf8_lossy(&self.buffer[..end]).into_owned();
+        self.buffer.drain(..end + len);
+        Some(line)
+    }
+
+    /// Interprets the given line, returning the event to dispatch if
+    /// the line is blank.
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // comment line
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Dispatches the event being built, if it has data.
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let mut event = mem::take(&mut self.event);
+        let mut data = mem::take(&mut self.data);
+
+        if data.is_empty() {
+            return None;
+        }
+
+        if data.ends_with('\n') {
+            data.pop();
+        }
+
+        if event.is_empty() {
+            event = DEFAULT_EVENT_TYPE.into();
+        }
+
+        Some(SseEvent {
+            event,
+            data,
+            id: self.id.clone(),
+            retry: self.retry,
+        })
+    }
+}
@@ -0,0 +1,21 @@
+//! Server-sent events (WHATWG HTML §9.2).
+//!
+//! A `text/event-stream` response body is a UTF-8 stream of events
+//! separated by blank lines. Each event is made of `field: value`
+//! lines:
+//!
+//! ```text
+//! event: update
+//! id: 42
+//! data: first line
+//! data: second line
+//!
+//! ```
+//!
+//! The specification is not an RFC: it lives in the [HTML Living
+//! Standard], and is commonly used for push notifications over a
+//! long-lived HTTP response.
+//!
+//! [HTML Living Standard]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+pub mod event_stream;
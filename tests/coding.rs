@@ -0,0 +1,333 @@
+//! Tests for content codings (RFC 9110 §8.4.1).
+//!
+//! All tests drive [`ContentDecode`] against a pre-crafted in-memory
+//! buffer via [`stub::StubStream`]. No network connection is made.
+
+#![cfg(feature = "compression")]
+
+mod stub;
+
+use io_http::{
+    rfc9110::{
+        coding::{ContentCoding, ContentDecode, ContentDecodeError, ContentDecodeResult},
+        request::HttpRequest,
+    },
+    rfc9112::{
+        chunk::HttpChunksRead,
+        framing::BodyFraming,
+        send::{Http11Send, Http11SendError, Http11SendResult},
+    },
+};
+use io_socket::{coroutines::read::SocketRead, runtimes::std_stream::handle};
+use url::Url;
+
+use crate::stub::StubStream;
+
+/// `hello world`, gzip encoded.
+const GZIP: &[u8] = &[
+    31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 203, 72, 205, 201, 201, 87, 40, 207, 47, 202, 73, 1, 0, 133,
+    17, 74, 13, 11, 0, 0, 0,
+];
+
+/// `hello world`, zlib encoded.
+const ZLIB: &[u8] = &[
+    120, 156, 203, 72, 205, 201, 201, 87, 40, 207, 47, 202, 73, 1, 0, 26, 11, 4, 93,
+];
+
+/// `hello world`, raw deflate encoded.
+const RAW_DEFLATE: &[u8] = &[203, 72, 205, 201, 201, 87, 40, 207, 47, 202, 73, 1, 0];
+
+fn test_result(mut decode: ContentDecode, stream: &mut StubStream) -> ContentDecodeResult {
+    let mut arg = None;
+
+    loop {
+        match decode.resume(arg.take()) {
+            ContentDecodeResult::Io { input } => arg = Some(handle(&mut *stream, input).unwrap()),
+            any => return any,
+        }
+    }
+}
+
+fn test(decode: ContentDecode, stream: &mut StubStream) -> Vec<u8> {
+    match test_result(decode, stream) {
+        ContentDecodeResult::Ok { body } => body,
+        ContentDecodeResult::Err { err } => panic!("unexpected error: {err}"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+fn test_send(send: Http11Send, stream: &mut StubStream) -> Http11SendResult {
+    let mut send = send;
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut *stream, input).unwrap()),
+            any => return any,
+        }
+    }
+}
+
+fn request() -> HttpRequest {
+    let url = Url::parse("http://example.com/").unwrap();
+    HttpRequest::get(url).header("Host", "example.com")
+}
+
+/// Encodes the given bytes using chunked transfer coding, with
+/// chunks of the given size.
+fn chunked(bytes: &[u8], size: usize) -> Vec<u8> {
+    let mut encoded = Vec::new();
+
+    for chunk in bytes.chunks(size) {
+        encoded.extend(format!("{:x}\r\n", chunk.len()).as_bytes());
+        encoded.extend(chunk);
+        encoded.extend(b"\r\n");
+    }
+
+    encoded.extend(b"0\r\n\r\n");
+    encoded
+}
+
+#[test]
+fn gzip_until_close() {
+    let decode = ContentDecode::new(ContentCoding::Gzip, SocketRead::default());
+    assert_eq!(test(decode, &mut StubStream::new(GZIP)), b"hello world");
+}
+
+#[test]
+fn gzip_lengthed_ignores_following_bytes() {
+    let mut body = GZIP.to_vec();
+    body.extend(b"HTTP/1.1 200 OK\r\n");
+
+    let decode = ContentDecode::lengthed(ContentCoding::Gzip, SocketRead::default(), GZIP.len());
+    assert_eq!(test(decode, &mut StubStream::new(&body)), b"hello world");
+}
+
+#[test]
+fn gzip_lengthed_pre_seeded() {
+    let mut decode =
+        ContentDecode::lengthed(ContentCoding::Gzip, SocketRead::default(), GZIP.len());
+    decode.extend(GZIP[..12].iter().copied());
+    assert_eq!(
+        test(decode, &mut StubStream::new(&GZIP[12..])),
+        b"hello world"
+    );
+}
+
+#[test]
+fn gzip_chunked() {
+    let body = chunked(GZIP, 4);
+    let decode = ContentDecode::chunked(
+        ContentCoding::Gzip,
+        HttpChunksRead::new(SocketRead::default()),
+    );
+    assert_eq!(test(decode, &mut StubStream::new(&body)), b"hello world");
+}
+
+#[test]
+fn gzip_one_byte_per_read() {
+    let decode = ContentDecode::lengthed(ContentCoding::Gzip, SocketRead::default(), GZIP.len());
+    let mut stream = StubStream::new(GZIP).read_size(1);
+    assert_eq!(test(decode, &mut stream), b"hello world");
+}
+
+#[test]
+fn gzip_multiple_members_split_at_read_boundary() {
+    let body = [GZIP, GZIP].concat();
+    let decode = ContentDecode::new(ContentCoding::Gzip, SocketRead::default());
+    let mut stream = StubStream::new(&body).read_size(GZIP.len());
+    assert_eq!(test(decode, &mut stream), b"hello worldhello world");
+}
+
+#[test]
+fn deflate_zlib() {
+    let decode = ContentDecode::new(ContentCoding::Deflate, SocketRead::default());
+    assert_eq!(test(decode, &mut StubStream::new(ZLIB)), b"hello world");
+}
+
+#[test]
+fn deflate_zlib_one_byte_per_read() {
+    let decode = ContentDecode::new(ContentCoding::Deflate, SocketRead::default());
+    let mut stream = StubStream::new(ZLIB).read_size(1);
+    assert_eq!(test(decode, &mut stream), b"hello world");
+}
+
+#[test]
+fn deflate_raw() {
+    let decode = ContentDecode::new(ContentCoding::Deflate, SocketRead::default());
+    assert_eq!(
+        test(decode, &mut StubStream::new(RAW_DEFLATE)),
+        b"hello world"
+    );
+}
+
+#[test]
+fn err_on_truncated_gzip() {
+    let decode = ContentDecode::new(ContentCoding::Gzip, SocketRead::default());
+
+    match test_result(decode, &mut StubStream::new(&GZIP[..20])) {
+        ContentDecodeResult::Err {
+            err: ContentDecodeError::UnexpectedEof,
+        } => {}
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_corrupted_gzip_checksum() {
+    let mut body = GZIP.to_vec();
+    let crc = body.len() - 8;
+    body[crc] ^= 0xff;
+
+    let decode = ContentDecode::new(ContentCoding::Gzip, SocketRead::default());
+
+    match test_result(decode, &mut StubStream::new(&body)) {
+        ContentDecodeResult::Err {
+            err: ContentDecodeError::InvalidGzipTrailer,
+        } => {}
+        other => panic!("expected invalid trailer error, got: {other:?}"),
+    }
+}
+
+/// 1 MiB of zeros, zlib encoded into about 1 KiB.
+fn zlib_bomb() -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(&[0; 1024 * 1024], 10)
+}
+
+#[test]
+fn err_on_ratio_exceeded() {
+    let bomb = zlib_bomb();
+    let decode = ContentDecode::new(ContentCoding::Deflate, SocketRead::default()).max_ratio(100);
+
+    match test_result(decode, &mut StubStream::new(&bomb)) {
+        ContentDecodeResult::Err {
+            err: ContentDecodeError::RatioExceeded(100),
+        } => (),
+        other => panic!("expected ratio exceeded, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_decoded_len_exceeded() {
+    let bomb = zlib_bomb();
+    let decode =
+        ContentDecode::new(ContentCoding::Deflate, SocketRead::default()).max_decoded_len(4096);
+
+    match test_result(decode, &mut StubStream::new(&bomb)) {
+        ContentDecodeResult::Err {
+            err: ContentDecodeError::DecodedLenExceeded(4096),
+        } => (),
+        other => panic!("expected decoded length exceeded, got: {other:?}"),
+    }
+}
+
+#[test]
+fn bomb_decoded_within_limits() {
+    let bomb = zlib_bomb();
+    let decode = ContentDecode::new(ContentCoding::Deflate, SocketRead::default())
+        .max_ratio(2000)
+        .max_decoded_len(1024 * 1024);
+
+    assert_eq!(test(decode, &mut StubStream::new(&bomb)).len(), 1024 * 1024);
+}
+
+#[test]
+fn transfer_gzip_chunked() {
+    let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n".to_vec();
+    response.extend(chunked(GZIP, 7));
+
+    let send = Http11Send::new(request());
+
+    match test_send(send, &mut StubStream::new(&response).read_size(5)) {
+        Http11SendResult::Ok {
+            response, framing, ..
+        } => {
+            assert_eq!(response.body, b"hello world");
+            assert_eq!(framing, BodyFraming::Chunked);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn transfer_gzip_chunked_trailers() {
+    let mut response =
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+    let mut body = chunked(GZIP, 7);
+    body.truncate(body.len() - 2);
+    body.extend(b"X-Checksum: abc\r\n\r\n");
+    response.extend(body);
+
+    let send = Http11Send::new(request());
+
+    match test_send(send, &mut StubStream::new(&response)) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.body, b"hello world");
+            assert_eq!(response.trailers, [("x-checksum".into(), "abc".into())]);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_transfer_coding_ratio_exceeded() {
+    let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: deflate, chunked\r\n\r\n".to_vec();
+    response.extend(chunked(&zlib_bomb(), 256));
+
+    let send = Http11Send::new(request()).max_decode_ratio(100);
+
+    match test_send(send, &mut StubStream::new(&response)) {
+        Http11SendResult::Err {
+            err: Http11SendError::ContentDecode(ContentDecodeError::RatioExceeded(100)),
+        } => (),
+        other => panic!("expected ratio exceeded, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_transfer_coding_decoded_len_exceeded() {
+    let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: deflate, chunked\r\n\r\n".to_vec();
+    response.extend(chunked(&zlib_bomb(), 256));
+
+    let send = Http11Send::new(request()).max_decoded_len(4096);
+
+    match test_send(send, &mut StubStream::new(&response)) {
+        Http11SendResult::Err {
+            err: Http11SendError::ContentDecode(ContentDecodeError::DecodedLenExceeded(4096)),
+        } => (),
+        other => panic!("expected decoded length exceeded, got: {other:?}"),
+    }
+}
+
+#[test]
+fn transfer_deflate_chunked() {
+    let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: deflate, chunked\r\n\r\n".to_vec();
+    response.extend(chunked(ZLIB, 4));
+
+    let send = Http11Send::new(request());
+
+    match test_send(send, &mut StubStream::new(&response)) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn transfer_gzip_chunked_framing_headers_rewritten() {
+    let mut response =
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n"
+            .to_vec();
+    response.extend(chunked(GZIP, 7));
+
+    let send = Http11Send::new(request()).rewrite_framing_headers(true);
+
+    match test_send(send, &mut StubStream::new(&response)) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.body, b"hello world");
+            assert_eq!(response.header("transfer-encoding"), None);
+            assert_eq!(response.header("content-length"), Some("11"));
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,144 @@
+//! Tests for the structured log events emitted by [`Http11Send`].
+//!
+//! A capturing logger records the key-value fields of each event
+//! while the coroutine is driven via [`stub::StubStream`].
+
+#![cfg(feature = "log")]
+
+mod stub;
+
+use std::{cell::RefCell, sync::Once};
+
+use io_http::{
+    rfc9110::request::HttpRequest,
+    rfc9112::send::{Http11Send, Http11SendResult},
+};
+use io_socket::runtimes::std_stream::handle;
+use log::{
+    Level, LevelFilter, Log, Metadata, Record,
+    kv::{Error, Key, Value, VisitSource},
+};
+use url::Url;
+
+use crate::stub::StubStream;
+
+/// A logged event: its message and its key-value fields.
+type Event = (String, Vec<(String, String)>);
+
+static LOGGER: CapturingLogger = CapturingLogger;
+static INIT: Once = Once::new();
+
+thread_local! {
+    /// Events logged by the current test, tests running on their own
+    /// thread.
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut fields = Fields(Vec::new());
+        record.key_values().visit(&mut fields).unwrap();
+
+        let message = record.args().to_string();
+        EVENTS.with_borrow_mut(|events| events.push((message, fields.0)));
+    }
+
+    fn flush(&self) {}
+}
+
+struct Fields(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+/// Drives the given coroutine against the given response, then
+/// returns the events logged meanwhile.
+fn test_send(mut send: Http11Send, response: &[u8]) -> Vec<Event> {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    let mut stream = StubStream::new(response);
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { .. } => break,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    EVENTS.take()
+}
+
+fn request() -> HttpRequest {
+    let url = Url::parse("http://example.com/path").unwrap();
+    HttpRequest::get(url).header("Host", "example.com")
+}
+
+fn field<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+    event
+        .1
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+#[test]
+fn request_and_response_fields() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+    let events = test_send(Http11Send::new(request()), response);
+
+    let sent = events
+        .iter()
+        .find(|(message, _)| message == "send HTTP/1.1 request")
+        .expect("missing request event");
+    assert_eq!(field(sent, "method"), Some("GET"));
+    assert_eq!(field(sent, "url"), Some("http://example.com/path"));
+
+    let received = events
+        .iter()
+        .find(|(message, _)| message == "receive HTTP/1.1 response")
+        .expect("missing response event");
+    assert_eq!(field(received, "status"), Some("200"));
+    assert_eq!(field(received, "body_len"), Some("5"));
+}
+
+const NON_UTF8_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nX-Name: caf\xE9\r\nContent-Length: 0\r\n\r\n";
+
+fn headers_event(events: &[Event]) -> &str {
+    events
+        .iter()
+        .find(|(message, _)| message.starts_with("HTTP/1.1 response headers:"))
+        .map(|(message, _)| message.as_str())
+        .expect("missing response headers event")
+}
+
+#[test]
+fn non_utf8_header_hex_rendered() {
+    let send = Http11Send::new(request()).hex_trace_headers(true);
+    let events = test_send(send, NON_UTF8_RESPONSE);
+    assert!(headers_event(&events).contains("X-Name: caf\\xE9\r\n"));
+}
+
+#[test]
+fn non_utf8_header_lossy_decoded_by_default() {
+    let events = test_send(Http11Send::new(request()), NON_UTF8_RESPONSE);
+    assert!(headers_event(&events).contains("X-Name: caf\u{FFFD}\r\n"));
+}
@@ -6,7 +6,7 @@
 mod stub;
 
 use io_http::{
-    rfc1945::send::{Http10Send, Http10SendResult},
+    rfc1945::send::{Http10Send, Http10SendError, Http10SendResult},
     rfc9110::request::HttpRequest,
 };
 use io_socket::runtimes::std_stream::handle;
@@ -41,6 +41,24 @@ fn http10_200_ok() {
     }
 }
 
+#[test]
+fn http10_request_line() {
+    let mut stream = StubStream::new(b"HTTP/1.0 204 No Content\r\n\r\n");
+    let url = Url::parse("http://example.com/path?q=1").unwrap();
+    let mut send = Http10Send::new(HttpRequest::get(url));
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http10SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http10SendResult::Ok { .. } => break,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    assert!(stream.written().starts_with(b"GET /path?q=1 HTTP/1.0\r\n"));
+}
+
 #[test]
 fn http10_version() {
     let response = b"HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n";
@@ -117,6 +135,38 @@ fn keep_alive_true_on_connection_keep_alive() {
     }
 }
 
+#[test]
+fn keep_alive_true_on_connection_keep_alive_mixed_case() {
+    let response = b"HTTP/1.0 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http10SendResult::Ok { keep_alive, .. } => assert!(keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_true_on_connection_token_list() {
+    let response =
+        b"HTTP/1.0 200 OK\r\nConnection: Upgrade, Keep-Alive\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http10SendResult::Ok { keep_alive, .. } => assert!(keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_false_on_repeated_connection_close() {
+    let response = b"HTTP/1.0 200 OK\r\nConnection: keep-alive\r\nConnection: close\r\n\
+        Content-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http10SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
 #[test]
 fn redirect_301_emits_redirect_result() {
     let response =
@@ -172,3 +222,30 @@ fn err_on_malformed_headers() {
         other => panic!("expected Err, got: {other:?}"),
     }
 }
+
+#[test]
+fn err_on_invalid_method() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let mut request = HttpRequest::get(url);
+    request.method = "GET /admin HTTP/1.0\r\n".into();
+
+    match Http10Send::new(request).resume(None) {
+        Http10SendResult::Err {
+            err: Http10SendError::InvalidMethod(method),
+        } => assert!(method.starts_with("GET /admin")),
+        other => panic!("expected invalid method error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_trailers() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let request = HttpRequest::get(url).trailer("X-Checksum", "abc");
+
+    match Http10Send::new(request).resume(None) {
+        Http10SendResult::Err {
+            err: Http10SendError::TrailersNotSupported,
+        } => {}
+        other => panic!("expected trailers not supported error, got: {other:?}"),
+    }
+}
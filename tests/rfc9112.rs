@@ -5,31 +5,59 @@
 
 mod stub;
 
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "std")]
+use io_http::rfc9112::chunk_iter::{HttpChunksIter, HttpChunksIterError};
 use io_http::{
-    rfc9110::request::HttpRequest,
+    rfc9110::{redirect::RedirectAction, request::HttpRequest},
     rfc9112::{
-        chunk::{HttpChunksRead, HttpChunksReadResult},
-        send::{Http11Send, Http11SendResult},
+        chunk::{HttpChunksRead, HttpChunksReadError, HttpChunksReadResult},
+        framing::{BodyFraming, RequestFraming},
+        send::{
+            Clock, Http11Send, Http11SendBuilder, Http11SendError, Http11SendPhase,
+            Http11SendResult, RequestComponents,
+        },
     },
 };
-use io_socket::{coroutines::read::SocketRead, runtimes::std_stream::handle};
+use io_socket::{
+    coroutines::read::SocketRead,
+    io::{SocketInput, SocketOutput},
+    runtimes::std_stream::handle,
+};
 use url::Url;
 
 use crate::stub::StubStream;
 
+fn request() -> HttpRequest {
+    let url = Url::parse("http://example.com/").unwrap();
+    HttpRequest::get(url).header("Host", "example.com")
+}
+
 fn test(response: &[u8]) -> Http11SendResult {
-    let mut stream = StubStream::new(response);
+    test_send(Http11Send::new(request()), response)
+}
 
-    let url = Url::parse("http://example.com/").unwrap();
-    let request = HttpRequest::get(url).header("Host", "example.com");
+fn test_send(send: Http11Send, response: &[u8]) -> Http11SendResult {
+    test_written(send, response).0
+}
 
-    let mut send = Http11Send::new(request);
+/// Drives the coroutine, returning its result alongside the bytes
+/// written to the stream.
+fn test_written(mut send: Http11Send, response: &[u8]) -> (Http11SendResult, Vec<u8>) {
+    let mut stream = StubStream::new(response);
     let mut arg = None;
 
     loop {
         match send.resume(arg.take()) {
             Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
-            any => return any,
+            any => return (any, stream.written().to_vec()),
         }
     }
 }
@@ -44,6 +72,53 @@ fn http11_200_ok() {
     }
 }
 
+#[test]
+fn http11_reason_phrase() {
+    let response = b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.reason, "Not Found"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn http11_non_standard_status() {
+    let response = b"HTTP/1.1 799 Weird\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(*response.status, 799);
+            assert_eq!(response.reason, "Weird");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_control_byte_in_header_value() {
+    let response = b"HTTP/1.1 200 OK\r\nX-Foo: a\x01b\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Err {
+            err: Http11SendError::ParseResponseHeaders(httparse::Error::HeaderValue),
+        } => {}
+        other => panic!("expected header value error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn non_utf8_header_value_decoded_lossily() {
+    let response = b"HTTP/1.1 200 OK\r\nX-Foo: caf\xe9\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.header("x-foo"), Some("caf\u{fffd}"));
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
 #[test]
 fn http11_version() {
     let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
@@ -72,248 +147,2865 @@ fn http10_response_version_and_connection() {
 }
 
 #[test]
-fn body_content_length() {
-    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world";
+fn headers_received_byte_by_byte() {
+    let mut response = String::from("HTTP/1.1 200 OK\r\n");
 
-    match test(response) {
-        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
-        other => panic!("unexpected result: {other:?}"),
+    for i in 0..50 {
+        response.push_str(&format!("X-Header-{i}: value-{i}\r\n"));
     }
+
+    response.push_str("Content-Length: 5\r\n\r\nhello");
+
+    let mut stream = StubStream::new(response.as_bytes()).read_size(1);
+    let mut send = Http11Send::new(request());
+    let mut arg = None;
+
+    let response = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { response, .. } => break response,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(response.header("x-header-49"), Some("value-49"));
+    assert_eq!(response.body, b"hello");
 }
 
 #[test]
-fn body_chunked() {
-    let response =
-        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+fn headers_with_bare_lf() {
+    let response = b"HTTP/1.1 200 OK\nContent-Length: 5\n\nhello";
 
     match test(response) {
-        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello"),
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
 #[test]
-fn body_read_to_eof() {
-    let response = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello world";
+fn err_on_truncated_headers() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n";
 
     match test(response) {
-        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
-        other => panic!("unexpected result: {other:?}"),
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::ReceivingHeaders,
+                    bytes_so_far,
+                },
+        } => assert_eq!(bytes_so_far, response.len()),
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
     }
 }
 
 #[test]
-fn body_empty_on_204() {
-    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+fn folded_header_unfolded() {
+    let response =
+        b"HTTP/1.1 200 OK\r\nX-Folded: hello\r\n  big\r\n\tworld\r\nContent-Length: 0\r\n\r\n";
 
     match test(response) {
         Http11SendResult::Ok { response, .. } => {
-            assert_eq!(*response.status, 204);
-            assert!(response.body.is_empty());
+            assert_eq!(response.header("x-folded"), Some("hello big world"));
         }
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
 #[test]
-fn body_empty_on_304() {
-    let response = b"HTTP/1.1 304 Not Modified\r\n\r\n";
+fn err_on_folded_header_in_strict_mode() {
+    let send = Http11Send::new(request()).strict(true);
+    let response = b"HTTP/1.1 200 OK\r\nX-Folded: hello\r\n world\r\nContent-Length: 0\r\n\r\n";
 
-    match test(response) {
-        Http11SendResult::Ok { response, .. } => {
-            assert_eq!(*response.status, 304);
-            assert!(response.body.is_empty());
-        }
-        other => panic!("unexpected result: {other:?}"),
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::ObsoleteLineFolding(name),
+        } => assert_eq!(name, "X-Folded"),
+        other => panic!("expected obsolete line folding error, got: {other:?}"),
     }
 }
 
 #[test]
-fn body_chunked_ignored_on_http10_response() {
-    let response = b"HTTP/1.0 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+fn raw_headers_captured_in_order() {
+    let mut send = Http11Send::new(request()).capture_raw_headers(true);
+    let response = concat!(
+        "HTTP/1.1 200 OK\r\n",
+        "Set-Cookie: a=1\r\n",
+        "Content-Length: 0\r\n",
+        "set-cookie: b=2\r\n",
+        "X-Folded: a\r\n b\r\n",
+        "\r\n",
+    );
+    let mut stream = StubStream::new(response.as_bytes());
+    let mut arg = None;
 
-    match test(response) {
-        // Body must be the raw wire bytes, not the decoded chunk payload.
-        Http11SendResult::Ok { response, .. } => assert_ne!(response.body, b"hello"),
-        other => panic!("unexpected result: {other:?}"),
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { .. } => break,
+            other => panic!("unexpected result: {other:?}"),
+        }
     }
+
+    let expected = [
+        ("Set-Cookie".to_owned(), b"a=1".to_vec()),
+        ("Content-Length".to_owned(), b"0".to_vec()),
+        ("set-cookie".to_owned(), b"b=2".to_vec()),
+        ("X-Folded".to_owned(), b"a\r\n b".to_vec()),
+    ];
+    assert_eq!(send.raw_headers(), Some(&expected[..]));
 }
 
 #[test]
-fn keep_alive_true_by_default_on_http11() {
-    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+fn raw_headers_not_captured_by_default() {
+    let mut send = Http11Send::new(request());
+    let mut stream = StubStream::new(b"HTTP/1.1 204 No Content\r\nSet-Cookie: a=1\r\n\r\n");
+    let mut arg = None;
 
-    match test(response) {
-        Http11SendResult::Ok { keep_alive, .. } => assert!(keep_alive),
-        other => panic!("unexpected result: {other:?}"),
+    while let Http11SendResult::Io { input } = send.resume(arg.take()) {
+        arg = Some(handle(&mut stream, input).unwrap());
     }
+
+    assert_eq!(send.raw_headers(), None);
 }
 
 #[test]
-fn keep_alive_false_on_connection_close() {
-    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+fn http09_response_read_until_close() {
+    let send = Http11Send::new(request()).allow_http09(true);
+    let response = b"<html>\r\nhello\r\n\r\n</html>";
 
-    match test(response) {
-        Http11SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+    match test_send(send, response) {
+        Http11SendResult::Ok {
+            response,
+            keep_alive,
+            framing,
+            ..
+        } => {
+            assert_eq!(*response.status, 200);
+            assert_eq!(response.version, "HTTP/0.9");
+            assert!(response.headers.is_empty());
+            assert_eq!(response.body, b"<html>\r\nhello\r\n\r\n</html>");
+            assert!(!keep_alive);
+            assert_eq!(framing, BodyFraming::UntilClose);
+        }
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
-// ── Redirects ─────────────────────────────────────────────────────────────────
-
 #[test]
-fn redirect_301_emits_redirect_result() {
-    let response =
-        b"HTTP/1.1 301 Moved Permanently\r\nLocation: http://example.com/new\r\nContent-Length: 0\r\n\r\n";
+fn http11_response_parsed_when_http09_allowed() {
+    let send = Http11Send::new(request()).allow_http09(true);
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
 
-    match test(response) {
-        Http11SendResult::Redirect { url, response, .. } => {
-            assert_eq!(url.as_str(), "http://example.com/new");
-            assert_eq!(*response.status, 301);
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.version, "HTTP/1.1");
+            assert_eq!(response.body, b"hello");
         }
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
 #[test]
-fn redirect_same_origin() {
-    let response =
-        b"HTTP/1.1 302 Found\r\nLocation: http://example.com/other\r\nContent-Length: 0\r\n\r\n";
-
-    match test(response) {
-        Http11SendResult::Redirect { same_origin, .. } => assert!(same_origin),
-        other => panic!("unexpected result: {other:?}"),
-    }
+fn err_on_http09_response_by_default() {
+    let response = b"<html>\r\nhello\r\n\r\n</html>";
+    assert!(matches!(test(response), Http11SendResult::Err { .. }));
 }
 
 #[test]
-fn redirect_cross_origin_different_host() {
-    let response =
-        b"HTTP/1.1 302 Found\r\nLocation: http://other.com/\r\nContent-Length: 0\r\n\r\n";
+fn body_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world";
 
     match test(response) {
-        Http11SendResult::Redirect { same_origin, .. } => assert!(!same_origin),
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
 #[test]
-fn redirect_cross_origin_different_scheme() {
-    let response =
-        b"HTTP/1.1 302 Found\r\nLocation: https://example.com/\r\nContent-Length: 0\r\n\r\n";
+fn body_content_length_consistent_duplicates() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5, 5\r\nContent-Length: 5\r\n\r\nhello";
 
     match test(response) {
-        Http11SendResult::Redirect { same_origin, .. } => assert!(!same_origin),
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello"),
         other => panic!("unexpected result: {other:?}"),
     }
 }
 
 #[test]
-fn redirect_without_location_falls_through_to_ok() {
-    let response = b"HTTP/1.1 301 Moved Permanently\r\nContent-Length: 0\r\n\r\n";
+fn err_on_conflicting_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!";
 
     match test(response) {
-        Http11SendResult::Ok { response, .. } => assert_eq!(*response.status, 301),
-        other => panic!("unexpected result: {other:?}"),
+        Http11SendResult::Err {
+            err: Http11SendError::AmbiguousBodyFraming,
+        } => {}
+        other => panic!("expected ambiguous body framing error, got: {other:?}"),
     }
 }
 
 #[test]
-fn err_on_malformed_headers() {
-    let response = b"NOT HTTP AT ALL\r\n\r\n";
+fn err_on_invalid_content_length() {
+    let responses: [&[u8]; 3] = [
+        b"HTTP/1.1 200 OK\r\nContent-Length: 5, abc\r\nConnection: close\r\n\r\nhello",
+        b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Length: x\r\nConnection: close\r\n\r\nhello",
+        b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551616\r\nConnection: close\r\n\r\nhello",
+    ];
 
-    match test(response) {
-        Http11SendResult::Err { .. } => {}
-        other => panic!("expected Err, got: {other:?}"),
+    for response in responses {
+        match test(response) {
+            Http11SendResult::Err {
+                err: Http11SendError::AmbiguousBodyFraming,
+            } => {}
+            other => panic!("expected ambiguous body framing error, got: {other:?}"),
+        }
     }
 }
 
-fn test_chunks(encoded: &[u8]) -> Vec<u8> {
-    let mut stream = StubStream::new(encoded);
-    let mut http = HttpChunksRead::new(SocketRead::default());
+/// Drives the coroutine over a response with a body of `len` bytes,
+/// returning the number of reads performed.
+fn count_reads(mut send: Http11Send, len: usize) -> usize {
+    let mut response = format!("HTTP/1.1 200 OK\r\nContent-Length: {len}\r\n\r\n").into_bytes();
+    response.resize(response.len() + len, b'a');
+
+    let mut stream = StubStream::new(&response);
     let mut arg = None;
+    let mut reads = 0;
 
     loop {
-        match http.resume(arg.take()) {
-            HttpChunksReadResult::Ok { body } => return body,
-            HttpChunksReadResult::Err { err } => panic!("unexpected error: {err}"),
-            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => {
+                if matches!(input, SocketInput::Read { .. }) {
+                    reads += 1;
+                }
+                arg = Some(handle(&mut stream, input).unwrap());
+            }
+            Http11SendResult::Ok { response, .. } => {
+                assert_eq!(response.body.len(), len);
+                return reads;
+            }
+            other => panic!("unexpected result: {other:?}"),
         }
     }
 }
 
-/// Test case from the Russian Wikipedia page on chunked transfer
-/// encoding:
-/// <https://ru.wikipedia.org/wiki/Chunked_transfer_encoding>
 #[test]
-fn chunks_wiki_ru() {
-    let encoded = concat!(
-        "9\r\n",
-        "chunk 1, \r\n",
-        "7\r\n",
-        "chunk 2\r\n",
-        "0\r\n",
-        "\r\n",
-    );
-    assert_eq!(test_chunks(encoded.as_bytes()), b"chunk 1, chunk 2");
+fn body_content_length_large_in_few_reads() {
+    // one read for the headers, one for the rest of the body
+    assert_eq!(count_reads(Http11Send::new(request()), 32 * 1024), 2);
 }
 
-/// Test case from the French Wikipedia page on chunked transfer
-/// encoding:
-/// <https://fr.wikipedia.org/wiki/Chunked_transfer_encoding>
 #[test]
-fn chunks_wiki_fr() {
-    let encoded = concat!(
-        "27\r\n",
-        "Voici les données du premier morceau\r\n\r\n",
-        "1C\r\n",
-        "et voici un second morceau\r\n\r\n",
-        "20\r\n",
-        "et voici deux derniers morceaux \r\n",
-        "12\r\n",
-        "sans saut de ligne\r\n",
-        "0\r\n",
-        "\r\n",
-    );
-    let expected = concat!(
-        "Voici les données du premier morceau\r\n",
-        "et voici un second morceau\r\n",
-        "et voici deux derniers morceaux ",
-        "sans saut de ligne",
-    );
-    assert_eq!(test_chunks(encoded.as_bytes()), expected.as_bytes());
+fn body_content_length_capped_read_buffer() {
+    let send = Http11Send::new(request()).max_read_buffer_capacity(8 * 1024);
+    assert_eq!(count_reads(send, 32 * 1024), 5);
 }
 
-/// Test case from the frewsxcv/rust-chunked-transfer repository:
-/// <https://github.com/frewsxcv/rust-chunked-transfer/blob/main/src/decoder.rs>
 #[test]
-fn chunks_github_frewsxcv() {
-    assert_eq!(
-        test_chunks(b"3\r\nhel\r\nb\r\nlo world!!!\r\n0\r\n\r\n"),
-        b"hello world!!!"
-    );
+fn body_content_length_not_read_past() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloHTTP/1.1 200 OK\r\n";
+    let mut stream = StubStream::new(response).read_size(40);
+    let mut send = Http11Send::new(request());
+    let mut arg = None;
+
+    let body = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { response, .. } => break response.body,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello");
+
+    let mut rest = Vec::new();
+    std::io::Read::read_to_end(&mut stream, &mut rest).unwrap();
+    assert_eq!(rest, b"HTTP/1.1 200 OK\r\n");
+}
+
+/// Sends a request using the given read buffer, returning the buffer
+/// taken back and the number of reads.
+fn test_read_buffer(buf: Vec<u8>, response: &[u8]) -> (Vec<u8>, usize) {
+    let mut send = Http11Send::with_read_buffer(request(), buf);
+    let mut stream = StubStream::new(response);
+    let mut arg = None;
+    let mut reads = 0;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => {
+                if matches!(input, SocketInput::Read { .. }) {
+                    reads += 1;
+                }
+
+                arg = Some(handle(&mut stream, input).unwrap());
+            }
+            Http11SendResult::Ok { response, .. } => {
+                assert_eq!(response.body, b"hello world");
+                break;
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    (send.take_read_buffer().unwrap(), reads)
 }
 
 #[test]
-fn chunks_single() {
-    assert_eq!(test_chunks(b"5\r\nhello\r\n0\r\n\r\n"), b"hello");
+fn read_buffer_reused_across_requests() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world";
+    let buf = Vec::with_capacity(4096);
+    let ptr = buf.as_ptr();
+
+    let (buf, _) = test_read_buffer(buf, response);
+    assert_eq!(buf.as_ptr(), ptr);
+    assert_eq!(buf.capacity(), 4096);
+
+    let (buf, reads) = test_read_buffer(buf, response);
+    assert_eq!(buf.as_ptr(), ptr);
+    assert_eq!(reads, 1);
 }
 
 #[test]
-fn chunks_empty_body() {
-    assert_eq!(test_chunks(b"0\r\n\r\n"), b"");
+fn read_buffer_capacity_respected() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world";
+    let buf = Vec::with_capacity(8);
+    let ptr = buf.as_ptr();
+
+    let (buf, reads) = test_read_buffer(buf, response);
+    assert_eq!(buf.as_ptr(), ptr);
+    assert_eq!(buf.capacity(), 8);
+    assert_eq!(reads, response.len().div_ceil(8));
 }
 
 #[test]
-fn chunks_extension_ignored() {
-    assert_eq!(
-        test_chunks(b"5;ext=ignored\r\nhello\r\n0\r\n\r\n"),
-        b"hello"
-    );
+fn err_on_truncated_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello";
+
+    match test(response) {
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::ReceivingBody,
+                    bytes_so_far,
+                },
+        } => assert_eq!(bytes_so_far, 5),
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
+    }
 }
 
 #[test]
-fn chunks_size_hex() {
-    // 0x0a = 10 bytes
-    assert_eq!(test_chunks(b"a\r\n0123456789\r\n0\r\n\r\n"), b"0123456789");
+fn content_length_beyond_u32_not_read_until_close() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 4294967296\r\n\r\nhello";
+
+    match test(response) {
+        #[cfg(target_pointer_width = "64")]
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::ReceivingBody,
+                    bytes_so_far: 5,
+                },
+        } => (),
+        #[cfg(not(target_pointer_width = "64"))]
+        Http11SendResult::Err {
+            err: Http11SendError::ContentLengthTooLarge(4294967296),
+        } => (),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_chunked() {
+    let response =
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_chunked_trailers_kept_apart() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nContent-MD5: abc\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.header("content-md5"), None);
+            assert_eq!(
+                response.trailers,
+                [("content-md5".to_owned(), "abc".to_owned())]
+            );
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_chunked_trailers_merged() {
+    let send = Http11Send::new(request()).merge_trailers(true);
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nContent-MD5: abc\r\nContent-Length: 5\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.header("content-md5"), Some("abc"));
+            assert_eq!(response.header("content-length"), None);
+            assert!(response.trailers.is_empty());
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn builder_shared_by_cloned_requests() {
+    let builder = Http11SendBuilder::new().merge_trailers(true);
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nContent-MD5: abc\r\n\r\n";
+
+    for send in [builder.build(request()), builder.clone().build(request())] {
+        match test_send(send, response) {
+            Http11SendResult::Ok { response, .. } => {
+                assert_eq!(response.header("content-md5"), Some("abc"));
+                assert!(response.trailers.is_empty());
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn builder_strict() {
+    let builder = Http11SendBuilder::new().strict(true);
+    let response = b"HTTP/1.1 200 OK\r\n\r\nhello";
+
+    match test_send(builder.build(request()), response) {
+        Http11SendResult::Err {
+            err: Http11SendError::UnboundedBodyOnKeepAlive,
+        } => {}
+        other => panic!("expected unbounded body error, got: {other:?}"),
+    }
+}
+
+/// Drives the coroutine, handing every write of the request to the
+/// given closure instead of the stream.
+fn test_early_response(
+    mut send: Http11Send,
+    response: &[u8],
+    mut fail_write: impl FnMut(&mut Http11Send, Vec<u8>) -> Option<SocketOutput>,
+) -> Http11SendResult {
+    let mut stream = StubStream::new(response);
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } => arg = fail_write(&mut send, bytes),
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            any => return any,
+        }
+    }
+}
+
+#[test]
+fn early_response_on_write_eof() {
+    let send = Http11Send::new(request()).early_response(true);
+    let response = b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 0\r\n\r\n";
+
+    match test_early_response(send, response, |_, bytes| {
+        Some(SocketOutput::Wrote { bytes, n: 0 })
+    }) {
+        Http11SendResult::Ok {
+            response,
+            keep_alive,
+            ..
+        } => {
+            assert_eq!(*response.status, 413);
+            assert!(!keep_alive);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_write_eof_without_early_response() {
+    let response = b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 0\r\n\r\n";
+
+    match test_early_response(Http11Send::new(request()), response, |_, bytes| {
+        Some(SocketOutput::Wrote { bytes, n: 0 })
+    }) {
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::Sending,
+                    bytes_so_far,
+                },
+        } => assert_eq!(bytes_so_far, 0),
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_write_eof_mid_body_reports_bytes_written() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello world".to_vec();
+
+    let mut send = Http11Send::new(request);
+    let mut arg = None;
+
+    // the headers are written at once, then the body 3 bytes at a
+    // time until the connection is closed
+    let result = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } if bytes.starts_with(b"POST") => {
+                let n = bytes.len();
+                arg = Some(SocketOutput::Wrote { bytes, n });
+            }
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } => {
+                let n = if bytes.len() > 5 { 3 } else { 0 };
+                arg = Some(SocketOutput::Wrote { bytes, n });
+            }
+            other => break other,
+        }
+    };
+
+    let headers = b"POST / HTTP/1.1\r\nHost: example.com\r\ncontent-length: 11\r\n\r\n";
+
+    match result {
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::Sending,
+                    bytes_so_far,
+                },
+        } => assert_eq!(bytes_so_far, headers.len() + 6),
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn early_response_after_broken_pipe() {
+    let response = b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 6\r\n\r\ndenied";
+
+    // the runtime fails with a broken pipe: give up writing
+    match test_early_response(Http11Send::new(request()), response, |send, _| {
+        send.receive_response();
+        None
+    }) {
+        Http11SendResult::Ok {
+            response,
+            keep_alive,
+            ..
+        } => {
+            assert_eq!(*response.status, 401);
+            assert_eq!(response.body, b"denied");
+            assert!(!keep_alive);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn follow_keeps_configuration() {
+    let redirect = b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n";
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nContent-MD5: abc\r\n\r\n";
+
+    let mut send = Http11Send::new(request()).merge_trailers(true);
+    let mut stream = StubStream::new(redirect);
+    let mut arg = None;
+
+    let (request, response) = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Redirect { url, .. } => {
+                send = send.follow(HttpRequest::get(url));
+                stream = StubStream::new(response);
+            }
+            Http11SendResult::Ok {
+                request, response, ..
+            } => break (request, response),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(request.url.path(), "/next");
+    assert_eq!(response.header("content-md5"), Some("abc"));
+    assert!(response.trailers.is_empty());
+}
+
+#[test]
+fn body_chunked_in_coding_list() {
+    let response =
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: , Chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_chunked_not_last() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked, gzip\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Err {
+            err: Http11SendError::ChunkedNotLast,
+        } => {}
+        other => panic!("expected chunked not last error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_unsupported_transfer_coding() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: br, chunked\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Err {
+            err: Http11SendError::UnsupportedTransferCoding(coding),
+        } => assert_eq!(coding, "br"),
+        other => panic!("expected unsupported transfer coding error, got: {other:?}"),
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn err_on_gzip_transfer_coding_without_compression() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Err {
+            err: Http11SendError::UnsupportedTransferCoding(coding),
+        } => assert_eq!(coding, "gzip"),
+        other => panic!("expected unsupported transfer coding error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn body_read_to_eof() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello world";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_read_to_eof_not_truncated() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello world";
+
+    match test(response) {
+        Http11SendResult::Ok {
+            response,
+            truncated,
+            ..
+        } => {
+            assert_eq!(response.body, b"hello world");
+            assert!(!truncated);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_read_to_eof_truncated_on_abrupt_close() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello";
+    let mut stream = StubStream::new(response).read_size(8);
+    let mut send = Http11Send::new(request());
+    let mut arg = None;
+
+    let (response, truncated) = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => match handle(&mut stream, input).unwrap() {
+                // the runtime detects an unclean close, e.g. a TLS
+                // connection closed without close_notify
+                SocketOutput::Read { n: 0, .. } => send.close_abruptly(),
+                output => arg = Some(output),
+            },
+            Http11SendResult::Ok {
+                response,
+                truncated,
+                ..
+            } => break (response, truncated),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(response.body, b"hello");
+    assert!(truncated);
+}
+
+#[test]
+fn close_abruptly_ignored_on_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello";
+    let mut stream = StubStream::new(response);
+    let mut send = Http11Send::new(request());
+    let mut arg = None;
+
+    let result = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => match handle(&mut stream, input).unwrap() {
+                SocketOutput::Read { n: 0, buf } => {
+                    send.close_abruptly();
+                    arg = Some(SocketOutput::Read { n: 0, buf });
+                }
+                output => arg = Some(output),
+            },
+            any => break any,
+        }
+    };
+
+    match result {
+        Http11SendResult::Err {
+            err:
+                Http11SendError::UnexpectedEof {
+                    phase: Http11SendPhase::ReceivingBody,
+                    bytes_so_far,
+                },
+        } => assert_eq!(bytes_so_far, 5),
+        other => panic!("expected unexpected EOF error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn body_empty_on_204() {
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(*response.status, 204);
+            assert!(response.body.is_empty());
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_empty_on_304() {
+    let response = b"HTTP/1.1 304 Not Modified\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(*response.status, 304);
+            assert!(response.body.is_empty());
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_chunked_ignored_on_http10_response() {
+    let response = b"HTTP/1.0 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+
+    match test(response) {
+        // Body must be the raw wire bytes, not the decoded chunk payload.
+        Http11SendResult::Ok { response, .. } => assert_ne!(response.body, b"hello"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    match test(response) {
+        Http11SendResult::Ok { framing, .. } => assert_eq!(framing, BodyFraming::ContentLength(5)),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_chunked() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { framing, .. } => assert_eq!(framing, BodyFraming::Chunked),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_until_close() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello";
+
+    match test(response) {
+        Http11SendResult::Ok { framing, .. } => assert_eq!(framing, BodyFraming::UntilClose),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_empty() {
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { framing, .. } => assert_eq!(framing, BodyFraming::Empty),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn not_modified_on_304() {
+    let response = b"HTTP/1.1 304 Not Modified\r\nETag: \"abc\"\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { not_modified, .. } => assert!(not_modified),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn not_modified_false_on_200() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    match test(response) {
+        Http11SendResult::Ok { not_modified, .. } => assert!(!not_modified),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_true_by_default_on_http11() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_false_on_connection_close() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_false_on_connection_close_in_token_list() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: Upgrade, Close\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_false_by_default_on_http10() {
+    let response = b"HTTP/1.0 200 OK\r\nConnection: Upgrade\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn keep_alive_true_on_http10_connection_keep_alive() {
+    let response = b"HTTP/1.0 200 OK\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_read_to_eof_on_keep_alive_when_lenient() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: keep-alive\r\n\r\nhello world";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_unbounded_body_on_keep_alive_when_strict() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: keep-alive\r\n\r\nhello world";
+    let send = Http11Send::new(request()).strict(true);
+
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::UnboundedBodyOnKeepAlive,
+        } => {}
+        other => panic!("expected unbounded body error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn body_read_to_eof_on_connection_close_when_strict() {
+    let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello world";
+    let send = Http11Send::new(request()).strict(true);
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello world"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+// ── Redirects ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn redirect_301_emits_redirect_result() {
+    let response =
+        b"HTTP/1.1 301 Moved Permanently\r\nLocation: http://example.com/new\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Redirect { url, response, .. } => {
+            assert_eq!(url.as_str(), "http://example.com/new");
+            assert_eq!(*response.status, 301);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_same_origin() {
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://example.com/other\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Redirect { same_origin, .. } => assert!(same_origin),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_cross_origin_different_host() {
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://other.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Redirect { same_origin, .. } => assert!(!same_origin),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_cross_origin_different_scheme() {
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: https://example.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Redirect { same_origin, .. } => assert!(!same_origin),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_resolves_query_only_location() {
+    let url = Url::parse("http://example.com/search?page=1").unwrap();
+    let send = Http11Send::new(HttpRequest::get(url));
+    let response = b"HTTP/1.1 302 Found\r\nLocation: ?page=2\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Redirect { url, .. } => {
+            assert_eq!(url.as_str(), "http://example.com/search?page=2");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_resolves_scheme_relative_location() {
+    let response = b"HTTP/1.1 302 Found\r\nLocation: //other.com/path\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Redirect {
+            url, same_origin, ..
+        } => {
+            assert_eq!(url.as_str(), "http://other.com/path");
+            assert!(!same_origin);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_https_downgrade_allowed_by_default() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let send = Http11Send::new(HttpRequest::get(url));
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://example.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Redirect { url, .. } => assert_eq!(url.scheme(), "http"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_https_downgrade_when_forbidden() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let send = Http11Send::new(HttpRequest::get(url)).forbid_https_downgrade(true);
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://example.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::InsecureRedirect(url),
+        } => assert_eq!(url.as_str(), "http://example.com/"),
+        other => panic!("expected insecure redirect error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_policy_stops_on_cross_host_redirect() {
+    let send = Http11Send::new(request()).redirect_policy(|request, _, url| {
+        if request.url.host() == url.host() {
+            RedirectAction::Follow
+        } else {
+            RedirectAction::Stop
+        }
+    });
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://other.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(*response.status, 302),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_policy_follows_same_host_redirect() {
+    let send = Http11Send::new(request()).redirect_policy(|request, _, url| {
+        if request.url.host() == url.host() {
+            RedirectAction::Follow
+        } else {
+            RedirectAction::Stop
+        }
+    });
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://example.com/other\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Redirect { url, .. } => {
+            assert_eq!(url.as_str(), "http://example.com/other");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_redirect_refused_by_policy() {
+    let send = Http11Send::new(request()).redirect_policy(|_, _, _| RedirectAction::Error);
+    let response =
+        b"HTTP/1.1 302 Found\r\nLocation: http://other.com/\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::RedirectRefused(url),
+        } => assert_eq!(url.as_str(), "http://other.com/"),
+        other => panic!("expected redirect refused error, got: {other:?}"),
+    }
+}
+
+fn test_redirect_scheme(location: &str) -> Http11SendResult {
+    let response =
+        format!("HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\n\r\n");
+    test(response.as_bytes())
+}
+
+#[test]
+fn err_on_javascript_redirect() {
+    match test_redirect_scheme("javascript:alert(1)") {
+        Http11SendResult::Err {
+            err: Http11SendError::UnsupportedRedirectScheme(scheme),
+        } => assert_eq!(scheme, "javascript"),
+        other => panic!("expected unsupported redirect scheme error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_data_redirect() {
+    match test_redirect_scheme("data:text/html;base64,PHNjcmlwdD4=") {
+        Http11SendResult::Err {
+            err: Http11SendError::UnsupportedRedirectScheme(scheme),
+        } => assert_eq!(scheme, "data"),
+        other => panic!("expected unsupported redirect scheme error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_to_https_scheme_supported() {
+    match test_redirect_scheme("HTTPS://example.com/") {
+        Http11SendResult::Redirect { url, .. } => assert_eq!(url.scheme(), "https"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn redirect_without_location_falls_through_to_ok() {
+    let response = b"HTTP/1.1 301 Moved Permanently\r\nContent-Length: 0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(*response.status, 301),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn multiple_choices_without_location_returned_as_is() {
+    let response = b"HTTP/1.1 300 Multiple Choices\r\nContent-Length: 7\r\n\r\na, b, c";
+    let send = Http11Send::new(request()).redirect_policy(|_, _, _| RedirectAction::Error);
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(*response.status, 300);
+            assert_eq!(response.body, b"a, b, c");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_malformed_headers() {
+    let response = b"NOT HTTP AT ALL\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Err { .. } => {}
+        other => panic!("expected Err, got: {other:?}"),
+    }
+}
+
+// ── Request trailers ──────────────────────────────────────────────────────────
+
+#[test]
+fn request_trailers_sent_chunked() {
+    let request = request()
+        .body(b"hello world".to_vec())
+        .trailer("X-Checksum", "5eb63bbbe01eeed093cb22bb8f5acdc3");
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    let (result, written) = test_written(Http11Send::new(request), response);
+
+    match result {
+        Http11SendResult::Ok { .. } => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let end = written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    let headers = String::from_utf8_lossy(&written[..end]);
+    assert!(headers.contains("transfer-encoding: chunked\r\n"));
+    assert!(headers.contains("trailer: X-Checksum\r\n"));
+    assert!(!headers.contains("content-length"));
+
+    // decode the body back with the chunked reader
+    let mut stream = StubStream::new(&written[end..]);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello world");
+    assert_eq!(
+        http.trailers(),
+        [(
+            "x-checksum".to_owned(),
+            "5eb63bbbe01eeed093cb22bb8f5acdc3".to_owned()
+        )]
+    );
+}
+
+/// Returns the request headers written to the stream.
+fn written_headers(send: Http11Send) -> String {
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    let (_, written) = test_written(send, response);
+    let end = written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    String::from_utf8_lossy(&written[..end]).into_owned()
+}
+
+#[test]
+fn content_length_omitted_on_empty_get() {
+    let headers = written_headers(Http11Send::new(request()));
+    assert!(!headers.contains("content-length"));
+}
+
+#[test]
+fn content_length_sent_on_empty_post() {
+    let mut request = request();
+    request.method = "POST".into();
+    let headers = written_headers(Http11Send::new(request));
+    assert!(headers.contains("content-length: 0\r\n"));
+}
+
+#[test]
+fn content_length_sent_on_empty_get_when_always() {
+    let send = Http11Send::new(request()).always_send_content_length(true);
+    let headers = written_headers(send);
+    assert!(headers.contains("content-length: 0\r\n"));
+}
+
+#[test]
+fn request_bytes_captured() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello".to_vec();
+
+    let mut send = Http11Send::new(request).capture_request_bytes(true);
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    let mut stream = StubStream::new(response);
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { .. } => break,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    let expected = b"POST / HTTP/1.1\r\nHost: example.com\r\ncontent-length: 5\r\n\r\nhello";
+    assert_eq!(send.request_bytes(), Some(&expected[..]));
+    assert_eq!(stream.written(), expected);
+}
+
+#[test]
+fn body_copied_apart_from_headers() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello".to_vec();
+    let body_ptr = request.body.as_ptr();
+
+    let mut send = Http11Send::new(request);
+    let mut stream = StubStream::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut writes = Vec::new();
+    let mut arg = None;
+
+    let request = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => {
+                if let SocketInput::Write { bytes } = &input {
+                    writes.push((bytes.clone(), bytes.as_ptr()));
+                }
+
+                arg = Some(handle(&mut stream, input).unwrap());
+            }
+            Http11SendResult::Ok { request, .. } => break request,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    let [(headers, headers_ptr), (body, ptr)] = &writes[..] else {
+        panic!("expected two writes, got: {writes:?}");
+    };
+
+    assert_eq!(
+        headers,
+        b"POST / HTTP/1.1\r\nHost: example.com\r\ncontent-length: 5\r\n\r\n"
+    );
+    assert_eq!(body, b"hello");
+
+    // the body is copied into the headers buffer, reused, rather
+    // than appended to the headers
+    assert_eq!(ptr, headers_ptr);
+
+    // the body never left the request
+    assert_eq!(request.body, b"hello");
+    assert_eq!(request.body.as_ptr(), body_ptr);
+}
+
+#[test]
+fn body_given_back_on_early_response() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello".to_vec();
+
+    let send = Http11Send::new(request).early_response(true);
+    let response = b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 0\r\n\r\n";
+
+    match test_early_response(send, response, |send, _| {
+        send.receive_response();
+        None
+    }) {
+        Http11SendResult::Ok { request, .. } => assert_eq!(request.body, b"hello"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+/// Writes the headers, then fails the first body write as a broken
+/// pipe would, giving up on the request.
+fn test_body_write_failure(send: &mut Http11Send) {
+    let mut stream = StubStream::new(b"");
+    let mut writes = 0;
+    let mut arg = None;
+
+    while let Http11SendResult::Io { input } = send.resume(arg.take()) {
+        if let SocketInput::Write { .. } = input {
+            writes += 1;
+
+            if writes == 2 {
+                return;
+            }
+        }
+
+        arg = Some(handle(&mut stream, input).unwrap());
+    }
+
+    panic!("expected the body to be written apart");
+}
+
+#[test]
+fn body_kept_on_early_response_mid_body() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello".to_vec();
+
+    let mut send = Http11Send::new(request).early_response(true);
+    test_body_write_failure(&mut send);
+    send.receive_response();
+
+    let response = b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { request, .. } => assert_eq!(request.body, b"hello"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_recovered_after_failure_mid_body() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello".to_vec();
+
+    let mut send = Http11Send::new(request);
+    test_body_write_failure(&mut send);
+
+    let request = send.into_request().unwrap();
+    assert_eq!(request.body, b"hello");
+}
+
+#[test]
+fn body_written_from_offset_on_partial_writes() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = b"hello world".to_vec();
+
+    let mut send = Http11Send::new(request);
+    let mut stream = StubStream::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut body = Vec::new();
+    let mut arg = None;
+
+    // the headers are written at once, the body 3 bytes at a time
+    let request = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } if bytes.starts_with(b"POST") => {
+                let n = bytes.len();
+                arg = Some(SocketOutput::Wrote { bytes, n });
+            }
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } => {
+                let n = bytes.len().min(3);
+                body.extend_from_slice(&bytes[..n]);
+                arg = Some(SocketOutput::Wrote { bytes, n });
+            }
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { request, .. } => break request,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello world");
+    assert_eq!(request.body, b"hello world");
+}
+
+#[test]
+fn request_written_from_offset_on_partial_writes() {
+    let mut request = request();
+    request.method = "POST".into();
+    request.body = (0..70_000).map(|i| i as u8).collect();
+
+    let mut send = Http11Send::new(request.clone());
+    let mut stream = StubStream::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut written = Vec::new();
+    let mut longest = 0;
+    let mut arg = None;
+
+    // the headers and the body are written 7 bytes at a time
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io {
+                input: SocketInput::Write { bytes },
+            } => {
+                longest = longest.max(bytes.len());
+                let n = bytes.len().min(7);
+                written.extend_from_slice(&bytes[..n]);
+                arg = Some(SocketOutput::Wrote { bytes, n });
+            }
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Ok { .. } => break,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    let mut expected =
+        b"POST / HTTP/1.1\r\nHost: example.com\r\ncontent-length: 70000\r\n\r\n".to_vec();
+    expected.extend(&request.body);
+
+    assert_eq!(written, expected);
+    assert_eq!(longest, 64 * 1024);
+}
+
+#[test]
+fn request_bytes_not_captured_by_default() {
+    let mut send = Http11Send::new(request());
+    let mut stream = StubStream::new(b"HTTP/1.1 204 No Content\r\n\r\n");
+    let mut arg = None;
+
+    while let Http11SendResult::Io { input } = send.resume(arg.take()) {
+        arg = Some(handle(&mut stream, input).unwrap());
+    }
+
+    assert_eq!(send.request_bytes(), None);
+}
+
+#[test]
+fn canonical_headers_lowercased_and_sorted() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let mut request = HttpRequest::get(url)
+        .header("X-Amz-Date", "20240101T000000Z")
+        .header("Host", "example.com")
+        .header("Accept", "text/html")
+        .header("accept", "Application/JSON")
+        .body(b"hi".to_vec());
+    request.method = "PUT".into();
+
+    let headers = written_headers(Http11Send::new(request).canonical_headers(true));
+
+    assert_eq!(
+        headers,
+        concat!(
+            "PUT / HTTP/1.1\r\n",
+            "accept: text/html\r\n",
+            "accept: Application/JSON\r\n",
+            "content-length: 2\r\n",
+            "host: example.com\r\n",
+            "x-amz-date: 20240101T000000Z\r\n",
+            "\r\n",
+        )
+    );
+}
+
+#[test]
+fn headers_kept_as_given_by_default() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let request = HttpRequest::get(url)
+        .header("X-Foo", "1")
+        .header("Host", "example.com");
+
+    let headers = written_headers(Http11Send::new(request));
+    assert_eq!(
+        headers,
+        "GET / HTTP/1.1\r\nX-Foo: 1\r\nHost: example.com\r\n\r\n"
+    );
+}
+
+fn test_host(url: &str, host: &str) -> Http11SendResult {
+    let url = Url::parse(url).unwrap();
+    let request = HttpRequest::get(url).header("Host", host);
+    let send = Http11Send::new(request).validate_host(true);
+    test_send(send, b"HTTP/1.1 204 No Content\r\n\r\n")
+}
+
+#[test]
+fn host_matching_url_authority() {
+    assert!(matches!(
+        test_host("http://example.com/", "Example.com"),
+        Http11SendResult::Ok { .. }
+    ));
+    assert!(matches!(
+        test_host("http://example.com:8080/", "example.com:8080"),
+        Http11SendResult::Ok { .. }
+    ));
+    assert!(matches!(
+        test_host("http://[::1]:8080/", "[::1]:8080"),
+        Http11SendResult::Ok { .. }
+    ));
+}
+
+#[test]
+fn host_matching_with_default_port_normalized() {
+    assert!(matches!(
+        test_host("http://example.com/", "example.com:80"),
+        Http11SendResult::Ok { .. }
+    ));
+    assert!(matches!(
+        test_host("https://example.com:443/", "example.com"),
+        Http11SendResult::Ok { .. }
+    ));
+}
+
+#[test]
+fn err_on_host_mismatch() {
+    for (url, host) in [
+        ("http://b.com/", "a.com"),
+        ("http://example.com/", "example.com:8080"),
+        ("https://example.com/", "example.com:80"),
+        ("http://[::1]/", "[::2]"),
+    ] {
+        match test_host(url, host) {
+            Http11SendResult::Err {
+                err: Http11SendError::HostMismatch(mismatch),
+            } => assert_eq!(mismatch, host),
+            other => panic!("expected host mismatch for {host}, got: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn err_on_duplicate_host() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let request = HttpRequest::get(url)
+        .header("Host", "example.com")
+        .header("Host", "evil.com");
+    let send = Http11Send::new(request).validate_host(true);
+
+    match test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n") {
+        (
+            Http11SendResult::Err {
+                err: Http11SendError::DuplicateHost,
+            },
+            written,
+        ) => assert!(written.is_empty()),
+        (other, _) => panic!("expected duplicate host, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_duplicate_ordered_host() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let headers = vec![
+        ("Host".into(), b"example.com".to_vec()),
+        ("host".into(), b"example.com".to_vec()),
+    ];
+    let send = Http11Send::with_ordered_headers(HttpRequest::get(url), headers).validate_host(true);
+
+    match test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n") {
+        (
+            Http11SendResult::Err {
+                err: Http11SendError::DuplicateHost,
+            },
+            written,
+        ) => assert!(written.is_empty()),
+        (other, _) => panic!("expected duplicate host, got: {other:?}"),
+    }
+}
+
+#[test]
+fn host_not_validated_by_default() {
+    let url = Url::parse("http://b.com/").unwrap();
+    let request = HttpRequest::get(url).header("Host", "a.com");
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+
+    assert!(matches!(
+        test_send(Http11Send::new(request), response),
+        Http11SendResult::Ok { .. }
+    ));
+}
+
+/// Sends a verified TRACE request, the server echoing the given
+/// request message.
+fn test_trace(echo: &str) -> Http11SendResult {
+    let mut request = request().header("Authorization", "Bearer secret");
+    request.method = "TRACE".into();
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: message/http\r\nContent-Length: {}\r\n\r\n{echo}",
+        echo.len()
+    );
+
+    test_send(
+        Http11Send::new(request).verify_trace(true),
+        response.as_bytes(),
+    )
+}
+
+#[test]
+fn trace_echo_matching() {
+    let echo = "TRACE / HTTP/1.1\r\nHost: example.com\r\nAuthorization: Bearer secret\r\n\r\n";
+    assert!(matches!(test_trace(echo), Http11SendResult::Ok { .. }));
+
+    // sensitive fields may be left out of the echo
+    let echo = "TRACE / HTTP/1.1\r\nhost: example.com\r\n\r\n";
+    assert!(matches!(test_trace(echo), Http11SendResult::Ok { .. }));
+}
+
+#[test]
+fn err_on_trace_echo_altered() {
+    let echoes = [
+        "TRACE / HTTP/1.1\r\nHost: example.com\r\nVia: 1.1 proxy\r\n\r\n",
+        "TRACE / HTTP/1.1\r\nHost: example.org\r\n\r\n",
+        "TRACE /other HTTP/1.1\r\nHost: example.com\r\n\r\n",
+        "TRACE / HTTP/1.1\r\n\r\n",
+        "not an HTTP message",
+    ];
+
+    for echo in echoes {
+        match test_trace(echo) {
+            Http11SendResult::Err {
+                err: Http11SendError::TraceMismatch,
+            } => (),
+            other => panic!("expected TRACE mismatch for {echo:?}, got: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn trace_echo_not_verified_by_default() {
+    let mut request = request();
+    request.method = "TRACE".into();
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\naltered";
+
+    assert!(matches!(
+        test_send(Http11Send::new(request), response),
+        Http11SendResult::Ok { .. }
+    ));
+}
+
+#[test]
+fn http11_request_line() {
+    let url = Url::parse("http://example.com/path?q=1").unwrap();
+    let headers = written_headers(Http11Send::new(HttpRequest::get(url)));
+    assert!(headers.starts_with("GET /path?q=1 HTTP/1.1\r\n"));
+}
+
+#[test]
+fn options_asterisk_form() {
+    let mut request = request();
+    request.method = "OPTIONS".into();
+    let headers = written_headers(Http11Send::new(request).asterisk_form(true));
+    assert!(headers.starts_with("OPTIONS * HTTP/1.1\r\n"));
+}
+
+#[test]
+fn asterisk_form_ignored_on_get() {
+    let headers = written_headers(Http11Send::new(request()).asterisk_form(true));
+    assert!(headers.starts_with("GET / HTTP/1.1\r\n"));
+}
+
+#[test]
+fn connect_tunnel_established() {
+    let url = Url::parse("http://proxy.example.com:3128").unwrap();
+    let mut request = HttpRequest::get(url).header("Host", "proxy.example.com:3128");
+    request.method = "CONNECT".into();
+    let response = b"HTTP/1.1 200 Connection Established\r\n\r\n\x16\x03\x01";
+    let (result, written) = test_written(Http11Send::new(request), response);
+
+    assert!(written.starts_with(b"CONNECT proxy.example.com:3128 HTTP/1.1\r\n"));
+
+    match result {
+        Http11SendResult::Tunnel { response, buf, .. } => {
+            assert_eq!(*response.status, 200);
+            assert!(response.body.is_empty());
+            assert_eq!(buf, b"\x16\x03\x01");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn connect_refused_reads_body() {
+    let url = Url::parse("http://proxy.example.com:3128").unwrap();
+    let mut request = HttpRequest::get(url);
+    request.method = "CONNECT".into();
+    let response = b"HTTP/1.1 403 Forbidden\r\nContent-Length: 6\r\n\r\ndenied";
+
+    match test_send(Http11Send::new(request), response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"denied"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_method_with_space() {
+    let mut request = request();
+    request.method = "GET /admin".into();
+
+    match test_send(Http11Send::new(request), b"") {
+        Http11SendResult::Err {
+            err: Http11SendError::InvalidMethod(method),
+        } => assert_eq!(method, "GET /admin"),
+        other => panic!("expected invalid method error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn custom_method_token_sent() {
+    let mut request = request();
+    request.method = "PROPFIND".into();
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    let (_, written) = test_written(Http11Send::new(request), response);
+
+    assert!(written.starts_with(b"PROPFIND / HTTP/1.1\r\n"));
+}
+
+#[test]
+fn err_on_forbidden_request_trailer() {
+    let request = request().trailer("Content-Length", "0");
+
+    match test_send(Http11Send::new(request), b"") {
+        Http11SendResult::Err {
+            err: Http11SendError::ForbiddenTrailer(name),
+        } => assert_eq!(name, "Content-Length"),
+        other => panic!("expected forbidden trailer error, got: {other:?}"),
+    }
+}
+
+#[test]
+fn request_body_until_close() {
+    let request = request()
+        .header("Content-Length", "11")
+        .body(b"hello world".to_vec());
+    let send = Http11Send::new(request).body_framing(RequestFraming::UntilClose);
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+    let (result, written) = test_written(send, response);
+
+    match result {
+        Http11SendResult::Ok { keep_alive, .. } => assert!(!keep_alive),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let end = written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    let headers = String::from_utf8_lossy(&written[..end]);
+    assert!(headers.contains("connection: close\r\n"));
+    assert!(!headers.to_lowercase().contains("content-length"));
+    assert!(!headers.contains("transfer-encoding"));
+    assert_eq!(&written[end..], b"hello world");
+}
+
+#[test]
+fn request_body_chunked_without_trailers() {
+    let request = request().body(b"hello world".to_vec());
+    let send = Http11Send::new(request).body_framing(RequestFraming::Chunked);
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    let (_, written) = test_written(send, response);
+
+    let end = written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    let headers = String::from_utf8_lossy(&written[..end]);
+    assert!(headers.contains("transfer-encoding: chunked\r\n"));
+    assert!(!headers.contains("trailer"));
+    assert_eq!(test_chunks(&written[end..]), b"hello world");
+}
+
+fn test_chunks_result(encoded: &[u8]) -> HttpChunksReadResult {
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            any => return any,
+        }
+    }
+}
+
+fn test_chunks(encoded: &[u8]) -> Vec<u8> {
+    match test_chunks_result(encoded) {
+        HttpChunksReadResult::Ok { body } => body,
+        HttpChunksReadResult::Err { err } => panic!("unexpected error: {err}"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+/// Test case from the Russian Wikipedia page on chunked transfer
+/// encoding:
+/// <https://ru.wikipedia.org/wiki/Chunked_transfer_encoding>
+#[test]
+fn chunks_wiki_ru() {
+    let encoded = concat!(
+        "9\r\n",
+        "chunk 1, \r\n",
+        "7\r\n",
+        "chunk 2\r\n",
+        "0\r\n",
+        "\r\n",
+    );
+    assert_eq!(test_chunks(encoded.as_bytes()), b"chunk 1, chunk 2");
+}
+
+/// Test case from the French Wikipedia page on chunked transfer
+/// encoding:
+/// <https://fr.wikipedia.org/wiki/Chunked_transfer_encoding>
+#[test]
+fn chunks_wiki_fr() {
+    let encoded = concat!(
+        "27\r\n",
+        "Voici les données du premier morceau\r\n\r\n",
+        "1C\r\n",
+        "et voici un second morceau\r\n\r\n",
+        "20\r\n",
+        "et voici deux derniers morceaux \r\n",
+        "12\r\n",
+        "sans saut de ligne\r\n",
+        "0\r\n",
+        "\r\n",
+    );
+    let expected = concat!(
+        "Voici les données du premier morceau\r\n",
+        "et voici un second morceau\r\n",
+        "et voici deux derniers morceaux ",
+        "sans saut de ligne",
+    );
+    assert_eq!(test_chunks(encoded.as_bytes()), expected.as_bytes());
+}
+
+/// Test case from the frewsxcv/rust-chunked-transfer repository:
+/// <https://github.com/frewsxcv/rust-chunked-transfer/blob/main/src/decoder.rs>
+#[test]
+fn chunks_github_frewsxcv() {
+    assert_eq!(
+        test_chunks(b"3\r\nhel\r\nb\r\nlo world!!!\r\n0\r\n\r\n"),
+        b"hello world!!!"
+    );
+}
+
+#[test]
+fn chunks_single() {
+    assert_eq!(test_chunks(b"5\r\nhello\r\n0\r\n\r\n"), b"hello");
+}
+
+#[test]
+fn chunks_empty_body() {
+    assert_eq!(test_chunks(b"0\r\n\r\n"), b"");
+}
+
+#[test]
+fn chunks_extension_ignored() {
+    assert_eq!(
+        test_chunks(b"5;ext=ignored\r\nhello\r\n0\r\n\r\n"),
+        b"hello"
+    );
+}
+
+#[test]
+fn chunks_size_hex() {
+    // 0x0a = 10 bytes
+    assert_eq!(test_chunks(b"a\r\n0123456789\r\n0\r\n\r\n"), b"0123456789");
+}
+
+#[test]
+fn chunks_size_trailing_whitespace_tolerated() {
+    let encoded = b"1a \r\nabcdefghijklmnopqrstuvwxyz\r\n0\r\n\r\n";
+    assert_eq!(test_chunks(encoded), b"abcdefghijklmnopqrstuvwxyz");
+}
+
+#[test]
+fn chunks_size_stray_character_rejected() {
+    let encoded = b"1a!\r\nabcdefghijklmnopqrstuvwxyz\r\n0\r\n\r\n";
+
+    match test_chunks_result(encoded) {
+        HttpChunksReadResult::Err {
+            err: HttpChunksReadError::InvalidChunkSize(size),
+        } => assert_eq!(size, "1a!"),
+        other => panic!("expected invalid chunk size, got: {other:?}"),
+    }
+}
+
+#[test]
+fn chunks_streaming_delivers_each_chunk() {
+    let encoded = b"5\r\nhello\r\n1\r\n \r\n5\r\nworld\r\n0\r\n\r\n";
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default()).streaming(true);
+    let mut chunks = Vec::new();
+    let mut arg = None;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Chunk { chunk } => chunks.push(chunk),
+            HttpChunksReadResult::Err { err } => panic!("unexpected error: {err}"),
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+        }
+    };
+
+    assert_eq!(chunks, [&b"hello"[..], b" ", b"world"]);
+    assert!(body.is_empty());
+}
+
+#[test]
+fn chunks_empty_body_single_read() {
+    let mut stream = StubStream::new(b"0\r\n\r\n");
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+    let mut reads = 0;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => {
+                reads += 1;
+                arg = Some(handle(&mut stream, input).unwrap());
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert!(body.is_empty());
+    assert_eq!(reads, 1);
+}
+
+fn count_chunks_reads(mut http: HttpChunksRead, encoded: &[u8]) -> usize {
+    let mut stream = StubStream::new(encoded);
+    let mut arg = None;
+    let mut reads = 0;
+
+    loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => {
+                assert_eq!(body.len(), 1000);
+                break reads;
+            }
+            HttpChunksReadResult::Io { input } => {
+                reads += 1;
+                arg = Some(handle(&mut stream, input).unwrap());
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn chunks_larger_read_buffer_fewer_reads() {
+    let mut encoded = b"a\r\n0123456789\r\n".repeat(100);
+    encoded.extend_from_slice(b"0\r\n\r\n");
+
+    let default = count_chunks_reads(HttpChunksRead::new(SocketRead::default()), &encoded);
+    let larger = HttpChunksRead::new(SocketRead::default()).read_buffer_capacity(4096);
+    let larger = count_chunks_reads(larger, &encoded);
+
+    assert_eq!(larger, 1);
+    assert!(larger < default);
+}
+
+#[test]
+fn err_on_chunks_missing_final_crlf() {
+    match test_chunks_result(b"5\r\nhello\r\n0\r\n") {
+        HttpChunksReadResult::Err {
+            err: HttpChunksReadError::UnexpectedEof,
+        } => (),
+        other => panic!("expected unexpected EOF, got: {other:?}"),
+    }
+}
+
+fn test_lenient_chunks(encoded: &[u8]) -> (HttpChunksReadResult, HttpChunksRead) {
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default()).lenient_trailer_eof(true);
+    let mut arg = None;
+
+    loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            any => return (any, http),
+        }
+    }
+}
+
+#[test]
+fn chunks_missing_final_crlf_when_lenient() {
+    match test_lenient_chunks(b"5\r\nhello\r\n0\r\n") {
+        (HttpChunksReadResult::Ok { body }, _) => assert_eq!(body, b"hello"),
+        (other, _) => panic!("unexpected result: {other:?}"),
+    }
+
+    match test_lenient_chunks(b"5\r\nhello\r\n0\r\nX-Foo: bar\r\n") {
+        (HttpChunksReadResult::Ok { body }, http) => {
+            assert_eq!(body, b"hello");
+            assert_eq!(http.trailers(), [("x-foo".to_owned(), "bar".to_owned())]);
+        }
+        (other, _) => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_chunks_truncated_trailer_when_lenient() {
+    match test_lenient_chunks(b"5\r\nhello\r\n0\r\nX-Foo: b") {
+        (
+            HttpChunksReadResult::Err {
+                err: HttpChunksReadError::UnexpectedEof,
+            },
+            _,
+        ) => (),
+        (other, _) => panic!("expected unexpected EOF, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_chunk_data_without_crlf() {
+    match test_chunks_result(b"5\r\nhelloXY0\r\n\r\n") {
+        HttpChunksReadResult::Err {
+            err: HttpChunksReadError::MissingChunkCrlf,
+        } => (),
+        other => panic!("expected missing chunk CRLF, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_malformed_chunk_sizes() {
+    let sizes = [
+        "",
+        "+5",
+        "-5",
+        "0x5",
+        " 5",
+        "fffffffffffffffff",
+        "ffffffffffffffff",
+        "7fffffffffffffff",
+        "1000001",
+    ];
+
+    for size in sizes {
+        let encoded = format!("{size}\r\nhello\r\n0\r\n\r\n");
+
+        match test_chunks_result(encoded.as_bytes()) {
+            HttpChunksReadResult::Err {
+                err: HttpChunksReadError::InvalidChunkSize(_),
+            } => (),
+            other => panic!("expected invalid chunk size for {size:?}, got: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn err_on_chunk_size_above_max() {
+    let mut stream = StubStream::new(b"6\r\nhello!\r\n0\r\n\r\n");
+    let mut http = HttpChunksRead::new(SocketRead::default()).max_chunk_size(5);
+    let mut arg = None;
+
+    let result = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            any => break any,
+        }
+    };
+
+    match result {
+        HttpChunksReadResult::Err {
+            err: HttpChunksReadError::InvalidChunkSize(size),
+        } => assert_eq!(size, "6"),
+        other => panic!("expected invalid chunk size, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_response_chunk_size_above_max() {
+    let send = Http11Send::new(request()).max_chunk_size(5);
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nhello!\r\n0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::HttpChunksRead(HttpChunksReadError::InvalidChunkSize(size)),
+        } => assert_eq!(size, "6"),
+        other => panic!("expected invalid chunk size, got: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_truncated_chunks() {
+    let encoded = b"5\r\nhello\r\n1;ext\r\n!\r\n0\r\nX-Foo: bar\r\n\r\n";
+
+    for len in 0..encoded.len() {
+        match test_chunks_result(&encoded[..len]) {
+            HttpChunksReadResult::Err { .. } => (),
+            other => panic!("expected error for {len} bytes, got: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn chunks_fragmented_at_every_read_size() {
+    let encoded = b"5\r\nhello\r\n1;ext\r\n!\r\n0\r\nX-Foo: bar\r\n\r\n";
+
+    for read_size in 1..encoded.len() {
+        let mut stream = StubStream::new(encoded).read_size(read_size);
+        let mut http = HttpChunksRead::new(SocketRead::default());
+        let mut arg = None;
+
+        let body = loop {
+            match http.resume(arg.take()) {
+                HttpChunksReadResult::Ok { body } => break body,
+                HttpChunksReadResult::Io { input } => {
+                    arg = Some(handle(&mut stream, input).unwrap())
+                }
+                other => panic!("unexpected result for read size {read_size}: {other:?}"),
+            }
+        };
+
+        assert_eq!(body, b"hello!");
+        assert_eq!(http.trailers(), [("x-foo".to_owned(), "bar".to_owned())]);
+    }
+}
+
+#[test]
+fn err_on_malformed_chunk_framing() {
+    let cases: [&[u8]; 6] = [
+        b"5\r\nhel\r\n",
+        b"0\r\nX-Foo\r\n\r\n",
+        b"\r\n\r\n",
+        b"1\r\n\r\n\r\n",
+        b"2\r\n\r\n",
+        b"\x00\r\n",
+    ];
+
+    for encoded in cases {
+        match test_chunks_result(encoded) {
+            HttpChunksReadResult::Err { .. } => (),
+            other => panic!("expected error for {encoded:?}, got: {other:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn chunks_iter_yields_each_chunk() {
+    let encoded = b"5\r\nhello\r\n1\r\n \r\n5\r\nworld\r\n0\r\nX-Foo: bar\r\n\r\n";
+    let stream = StubStream::new(encoded).read_size(3);
+    let mut chunks = HttpChunksIter::new(HttpChunksRead::new(SocketRead::default()), stream);
+
+    let body: Vec<Vec<u8>> = chunks.by_ref().map(Result::unwrap).collect();
+
+    assert_eq!(body, [&b"hello"[..], b" ", b"world"]);
+    assert_eq!(chunks.trailers(), [("x-foo".to_owned(), "bar".to_owned())]);
+    assert!(chunks.next().is_none());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn chunks_iter_stops_after_error() {
+    let stream = StubStream::new(b"5\r\nhello\r\nzz\r\n");
+    let mut chunks = HttpChunksIter::new(HttpChunksRead::new(SocketRead::default()), stream);
+
+    assert_eq!(chunks.next().unwrap().unwrap(), b"hello");
+
+    match chunks.next() {
+        Some(Err(HttpChunksIterError::HttpChunksRead(HttpChunksReadError::InvalidChunkSize(
+            size,
+        )))) => assert_eq!(size, "zz"),
+        other => panic!("expected invalid chunk size, got: {other:?}"),
+    }
+
+    assert!(chunks.next().is_none());
+}
+
+#[test]
+fn chunks_reset_between_bodies() {
+    let encoded = b"5\r\nhello\r\n0\r\nX-Foo: 1\r\n\r\n6\r\nworld!\r\n0\r\nX-Bar: 2\r\n\r\n";
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut bodies = Vec::new();
+    let mut reads = 0;
+
+    for _ in 0..2 {
+        let mut arg = None;
+
+        let body = loop {
+            match http.resume(arg.take()) {
+                HttpChunksReadResult::Ok { body } => break body,
+                HttpChunksReadResult::Io { input } => {
+                    reads += 1;
+                    arg = Some(handle(&mut stream, input).unwrap());
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        };
+
+        bodies.push((body, http.trailers().to_vec(), http.decoded_len()));
+        http.reset();
+    }
+
+    assert_eq!(
+        bodies,
+        [
+            (b"hello".to_vec(), vec![("x-foo".into(), "1".into())], 5),
+            (b"world!".to_vec(), vec![("x-bar".into(), "2".into())], 6),
+        ]
+    );
+
+    // the second body was already buffered by the first read
+    assert_eq!(reads, 1);
+}
+
+#[test]
+fn chunks_leftover_recovered() {
+    let encoded = b"5\r\nhello\r\n0\r\nX-Foo: 1\r\n\r\nHTTP/1.1 204 No Content\r\n\r\n";
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello");
+    assert_eq!(http.into_leftover(), b"HTTP/1.1 204 No Content\r\n\r\n");
+}
+
+#[test]
+fn chunks_trailer_bytes_exposed() {
+    let encoded = b"5\r\nhello\r\n0\r\nExpires: never\r\nX-Foo:  bar \r\n\r\n";
+    let mut stream = StubStream::new(encoded).read_size(7);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello");
+    assert_eq!(http.trailer_bytes(), b"Expires: never\r\nX-Foo:  bar \r\n");
+}
+
+#[test]
+fn chunks_trailer_bytes_empty_without_trailers() {
+    let mut stream = StubStream::new(b"5\r\nhello\r\n0\r\n\r\n");
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    while let HttpChunksReadResult::Io { input } = http.resume(arg.take()) {
+        arg = Some(handle(&mut stream, input).unwrap());
+    }
+
+    assert!(http.trailer_bytes().is_empty());
+}
+
+#[test]
+fn body_chunked_empty() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok {
+            response,
+            keep_alive,
+            ..
+        } => {
+            assert!(response.body.is_empty());
+            assert!(keep_alive);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn chunks_decoded_len_grows() {
+    let encoded = b"5\r\nhello\r\n1\r\n \r\n5\r\nworld\r\n0\r\n\r\n";
+    let mut stream = StubStream::new(encoded).read_size(4);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+    let mut lens = vec![http.decoded_len()];
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        if lens.last() != Some(&http.decoded_len()) {
+            lens.push(http.decoded_len());
+        }
+    };
+
+    assert_eq!(body, b"hello world");
+    assert_eq!(lens, [0, 5, 6, 11]);
+}
+
+#[test]
+fn chunks_decoded_len_in_streaming_mode() {
+    let encoded = b"5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default()).streaming(true);
+    let mut arg = None;
+    let mut lens = Vec::new();
+
+    loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { .. } => break,
+            HttpChunksReadResult::Chunk { .. } => lens.push(http.decoded_len()),
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    assert_eq!(lens, [5, 11]);
+    assert_eq!(http.decoded_len(), 11);
+}
+
+#[test]
+fn chunks_trailers_exposed() {
+    let encoded = b"5\r\nhello\r\n0\r\nExpires: never\r\nX-Foo: bar\r\n\r\n";
+    let mut stream = StubStream::new(encoded);
+    let mut http = HttpChunksRead::new(SocketRead::default());
+    let mut arg = None;
+
+    let body = loop {
+        match http.resume(arg.take()) {
+            HttpChunksReadResult::Ok { body } => break body,
+            HttpChunksReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert_eq!(body, b"hello");
+    assert_eq!(
+        http.trailers(),
+        [
+            ("expires".to_owned(), "never".to_owned()),
+            ("x-foo".to_owned(), "bar".to_owned()),
+        ]
+    );
+}
+
+/// Drives a coroutine pushing the body into a sink, 7 bytes at a
+/// time, returning its result alongside the segments pushed.
+fn test_sink(response: &[u8]) -> (Http11SendResult, Vec<Vec<u8>>) {
+    let segments = Arc::new(Mutex::new(Vec::new()));
+    let sink = segments.clone();
+    let mut send = Http11Send::new(request()).body_sink(move |bytes: &[u8]| {
+        sink.lock().unwrap().push(bytes.to_vec());
+        Ok::<_, std::io::Error>(())
+    });
+
+    let mut stream = StubStream::new(response).read_size(7);
+    let mut arg = None;
+
+    let result = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            any => break any,
+        }
+    };
+
+    let segments = segments.lock().unwrap().clone();
+    (result, segments)
+}
+
+#[test]
+fn body_pushed_into_sink() {
+    let responses: [&[u8]; 3] = [
+        b"HTTP/1.1 200 OK\r\nContent-Length: 26\r\n\r\nabcdefghijklmnopqrstuvwxyz",
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+          a\r\nabcdefghij\r\n10\r\nklmnopqrstuvwxyz\r\n0\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nabcdefghijklmnopqrstuvwxyz",
+    ];
+
+    for response in responses {
+        let Http11SendResult::Ok {
+            response: buffered, ..
+        } = test(response)
+        else {
+            panic!("unexpected buffered result");
+        };
+
+        let (result, segments) = test_sink(response);
+
+        match result {
+            Http11SendResult::Ok { response, .. } => assert!(response.body.is_empty()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        assert!(segments.len() > 1, "body pushed at once: {segments:?}");
+        assert_eq!(segments.concat(), buffered.body);
+    }
+}
+
+#[test]
+fn body_sink_ignores_bytes_past_content_length() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloHTTP/1.1";
+    let (_, segments) = test_sink(response);
+    assert_eq!(segments.concat(), b"hello");
+}
+
+#[test]
+fn err_on_body_sink_error() {
+    let send = Http11Send::new(request()).body_sink(|_: &[u8]| Err("disk full"));
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    match test_send(send, response) {
+        Http11SendResult::Err {
+            err: Http11SendError::BodySink(err),
+        } => assert_eq!(err.to_string(), "disk full"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn body_sink_kept_across_redirects() {
+    let redirect = b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 5\r\n\r\nmoved";
+    let last = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    let segments = Arc::new(Mutex::new(Vec::new()));
+    let sink = segments.clone();
+    let mut send = Http11Send::new(request()).body_sink(move |bytes: &[u8]| {
+        sink.lock().unwrap().push(bytes.to_vec());
+        Ok::<_, std::io::Error>(())
+    });
+
+    let mut stream = StubStream::new(redirect);
+    let mut arg = None;
+
+    let response = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Redirect { url, response, .. } => {
+                assert_eq!(response.body, b"moved");
+                send = send.follow(HttpRequest::get(url));
+                stream = StubStream::new(last);
+            }
+            Http11SendResult::Ok { response, .. } => break response,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    assert!(response.body.is_empty());
+    assert_eq!(segments.lock().unwrap().concat(), b"hello");
+}
+
+/// Drives the coroutine to its end, then recovers its request.
+fn test_into_request(mut send: Http11Send, response: &[u8]) -> Option<HttpRequest> {
+    let mut stream = StubStream::new(response);
+    let mut arg = None;
+
+    while let Http11SendResult::Io { input } = send.resume(arg.take()) {
+        arg = Some(handle(&mut stream, input).unwrap());
+    }
+
+    send.into_request()
+}
+
+#[test]
+fn request_recovered_after_unexpected_eof() {
+    let request = request().body(b"hello world".to_vec());
+    let send = Http11Send::new(request);
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhel";
+
+    let request = test_into_request(send, response).unwrap();
+    assert_eq!(request.url.as_str(), "http://example.com/");
+    assert_eq!(request.body, b"hello world");
+}
+
+#[test]
+fn request_recovered_after_refused_redirect() {
+    let send = Http11Send::new(request()).redirect_policy(|_, _, _| RedirectAction::Error);
+    let response = b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n";
+
+    let request = test_into_request(send, response).unwrap();
+    assert_eq!(request.url.as_str(), "http://example.com/");
+}
+
+#[test]
+fn request_body_recovered_while_sending_headers() {
+    let request = request().body(b"hello world".to_vec());
+    let mut send = Http11Send::new(request);
+
+    // the headers are written first, the body apart
+    let Http11SendResult::Io { .. } = send.resume(None) else {
+        panic!("expected headers write");
+    };
+
+    let request = send.into_request().unwrap();
+    assert_eq!(request.body, b"hello world");
+}
+
+#[test]
+fn no_request_recovered_after_success() {
+    let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+    assert!(test_into_request(Http11Send::new(request()), response).is_none());
+}
+
+const HOP_BY_HOP_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\n\
+    Connection: keep-alive, X-Conn-Only\r\n\
+    Keep-Alive: timeout=5\r\n\
+    Transfer-Encoding: chunked\r\n\
+    TE: trailers\r\n\
+    Trailer: X-Checksum\r\n\
+    Upgrade: h2c\r\n\
+    Proxy-Authenticate: Basic realm=\"proxy\"\r\n\
+    Proxy-Authorization: Basic Zm9vOmJhcg==\r\n\
+    X-Conn-Only: 1\r\n\
+    Content-Type: text/plain\r\n\
+    \r\n\
+    5\r\nhello\r\n0\r\n\r\n";
+
+#[test]
+fn hop_by_hop_headers_stripped() {
+    let send = Http11Send::new(request()).strip_hop_by_hop(true);
+
+    match test_send(send, HOP_BY_HOP_RESPONSE) {
+        Http11SendResult::Ok {
+            response, framing, ..
+        } => {
+            assert_eq!(framing, BodyFraming::Chunked);
+            assert_eq!(response.body, b"hello");
+
+            let names: Vec<&str> = response.headers.iter().map(|(k, _)| k.as_str()).collect();
+            assert_eq!(names, ["content-type"]);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn hop_by_hop_headers_kept_by_default() {
+    match test(HOP_BY_HOP_RESPONSE) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.header("transfer-encoding"), Some("chunked"));
+            assert_eq!(response.header("x-conn-only"), Some("1"));
+            assert_eq!(response.headers.len(), 10);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+/// Fake clock, advanced by the test.
+#[derive(Clone, Default)]
+struct FakeClock(Arc<AtomicU64>);
+
+impl FakeClock {
+    fn advance(&self, ms: u64) {
+        self.0.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.0.load(Ordering::SeqCst))
+    }
+}
+
+/// Drives a coroutine with a deadline of 100 ms, advancing the clock
+/// by 10 ms on each I/O, the response being read 8 bytes at a time.
+fn test_deadline(response: &[u8]) -> Http11SendResult {
+    let clock = FakeClock::default();
+    let deadline = Duration::from_millis(100);
+    let mut send = Http11Send::new(request()).with_deadline(clock.clone(), deadline);
+    let mut stream = StubStream::new(response).read_size(8);
+    let mut arg = None;
+
+    loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => {
+                arg = Some(handle(&mut stream, input).unwrap());
+                clock.advance(10);
+            }
+            any => break any,
+        }
+    }
+}
+
+#[test]
+fn response_received_before_deadline() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    match test_deadline(response) {
+        Http11SendResult::Ok { response, .. } => assert_eq!(response.body, b"hello"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_deadline_exceeded_mid_response() {
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n";
+    let mut response = response.to_vec();
+    response.extend([b'a'; 100]);
+
+    match test_deadline(&response) {
+        Http11SendResult::Err {
+            err: Http11SendError::DeadlineExceeded,
+        } => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn err_on_deadline_exceeded_after_redirect() {
+    let redirect = b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n";
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+    let clock = FakeClock::default();
+    let deadline = Duration::from_millis(100);
+    let mut send = Http11Send::new(request()).with_deadline(clock.clone(), deadline);
+    let mut stream = StubStream::new(redirect);
+    let mut arg = None;
+
+    let result = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => {
+                arg = Some(handle(&mut stream, input).unwrap());
+                clock.advance(10);
+            }
+            Http11SendResult::Redirect { url, .. } => {
+                // the deadline counts from the original request
+                clock.advance(80);
+                send = send.follow(HttpRequest::get(url));
+                stream = StubStream::new(response);
+            }
+            any => break any,
+        }
+    };
+
+    match result {
+        Http11SendResult::Err {
+            err: Http11SendError::DeadlineExceeded,
+        } => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_headers_rewritten_after_dechunking() {
+    let send = Http11Send::new(request()).rewrite_framing_headers(true);
+    let response =
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Type: text/plain\r\n\r\n\
+        5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+    match test_send(send, response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.body, b"hello world");
+            assert_eq!(response.header("transfer-encoding"), None);
+            assert_eq!(response.header("content-length"), Some("11"));
+            assert_eq!(response.header("content-type"), Some("text/plain"));
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn framing_headers_kept_by_default() {
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+
+    match test(response) {
+        Http11SendResult::Ok { response, .. } => {
+            assert_eq!(response.header("transfer-encoding"), Some("chunked"));
+            assert_eq!(response.header("content-length"), None);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn ordered_headers_written_verbatim() {
+    let url = Url::parse("http://example.com/upload").unwrap();
+    let mut request = HttpRequest::get(url).body(b"hello".to_vec());
+    request.method = "POST".into();
+    let headers = vec![
+        ("X-Amz-Date".into(), b"20260101T000000Z".to_vec()),
+        ("host".into(), b"example.com".to_vec()),
+        ("X-Raw".into(), b"caf\xE9".to_vec()),
+        (
+            "Authorization".into(),
+            b"AWS4-HMAC-SHA256 Signature=abc".to_vec(),
+        ),
+    ];
+    let send = Http11Send::with_ordered_headers(request, headers).canonical_headers(true);
+    let (_, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let expected: &[u8] = b"POST /upload HTTP/1.1\r\n\
+        X-Amz-Date: 20260101T000000Z\r\n\
+        host: example.com\r\n\
+        X-Raw: caf\xE9\r\n\
+        Authorization: AWS4-HMAC-SHA256 Signature=abc\r\n\
+        content-length: 5\r\n\
+        \r\n\
+        hello";
+
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn ordered_headers_keep_own_content_length() {
+    let url = Url::parse("http://example.com/upload").unwrap();
+    let mut request = HttpRequest::get(url).body(b"hello".to_vec());
+    request.method = "POST".into();
+    let headers = vec![
+        ("Content-Length".into(), b"5".to_vec()),
+        ("Host".into(), b"example.com".to_vec()),
+    ];
+    let send = Http11Send::with_ordered_headers(request, headers);
+    let (_, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let expected: &[u8] =
+        b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\nHost: example.com\r\n\r\nhello";
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn err_on_ordered_headers_wrong_content_length() {
+    let url = Url::parse("http://example.com/upload").unwrap();
+    let mut request = HttpRequest::get(url).body(b"hello".to_vec());
+    request.method = "POST".into();
+    let headers = vec![("Content-Length".into(), b"3".to_vec())];
+    let send = Http11Send::with_ordered_headers(request, headers);
+    let (result, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    match result {
+        Http11SendResult::Err {
+            err: Http11SendError::FramingFieldMismatch(name),
+        } => assert_eq!(name, "Content-Length"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    assert!(written.is_empty());
+}
+
+#[test]
+fn err_on_ordered_headers_content_length_with_trailers() {
+    let url = Url::parse("http://example.com/upload").unwrap();
+    let mut request = HttpRequest::get(url)
+        .body(b"hello".to_vec())
+        .trailer("X-Checksum", "abc");
+    request.method = "POST".into();
+    let headers = vec![("Content-Length".into(), b"5".to_vec())];
+    let send = Http11Send::with_ordered_headers(request, headers);
+    let (result, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    match result {
+        Http11SendResult::Err {
+            err: Http11SendError::FramingFieldMismatch(name),
+        } => assert_eq!(name, "Content-Length"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    assert!(written.is_empty());
+}
+
+#[test]
+fn ordered_headers_keep_own_chunked_framing() {
+    let url = Url::parse("http://example.com/upload").unwrap();
+    let mut request = HttpRequest::get(url)
+        .body(b"hello".to_vec())
+        .trailer("X-Checksum", "abc");
+    request.method = "POST".into();
+    let headers = vec![
+        ("transfer-encoding".into(), b"chunked".to_vec()),
+        ("Host".into(), b"example.com".to_vec()),
+    ];
+    let send = Http11Send::with_ordered_headers(request, headers);
+    let (_, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let expected: &[u8] = b"POST /upload HTTP/1.1\r\n\
+        transfer-encoding: chunked\r\n\
+        Host: example.com\r\n\
+        trailer: X-Checksum\r\n\
+        \r\n\
+        5\r\nhello\r\n0\r\nX-Checksum: abc\r\n\r\n";
+    assert_eq!(written, expected);
+}
+
+/// Stub signer covering the method, the target and the `Host` and
+/// `Content-Length` fields.
+fn stub_signer(components: &RequestComponents) -> Vec<(String, String)> {
+    let host = components.header("host").unwrap();
+    let len = components.header("content-length").unwrap();
+    let base = format!(
+        "{} {} {} {}",
+        components.method,
+        components.target,
+        String::from_utf8_lossy(host),
+        String::from_utf8_lossy(len),
+    );
+
+    vec![
+        (
+            "Signature-Input".into(),
+            r#"sig1=("@method" "@target-uri" "host" "content-length")"#.into(),
+        ),
+        ("Signature".into(), format!("sig1=:{base}:")),
+    ]
+}
+
+#[test]
+fn signature_fields_written_last() {
+    let url = Url::parse("http://example.com/upload?x=1").unwrap();
+    let mut request = HttpRequest::get(url)
+        .header("Host", "example.com")
+        .body(b"hello".to_vec());
+    request.method = "POST".into();
+
+    let send = Http11Send::new(request).sign_with(stub_signer);
+    let (_, written) = test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let expected: &[u8] = b"POST /upload?x=1 HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        content-length: 5\r\n\
+        Signature-Input: sig1=(\"@method\" \"@target-uri\" \"host\" \"content-length\")\r\n\
+        Signature: sig1=:POST /upload?x=1 example.com 5:\r\n\
+        \r\n\
+        hello";
+
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn signer_sees_ordered_headers() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let signer_seen = seen.clone();
+
+    let headers = vec![("X-B".into(), b"2".to_vec()), ("X-A".into(), b"1".to_vec())];
+    let send = Http11Send::with_ordered_headers(request(), headers).sign_with(move |c| {
+        let names = c.headers.iter().map(|(k, _)| k.to_string());
+        signer_seen.lock().unwrap().extend(names);
+        vec![]
+    });
+
+    test_written(send, b"HTTP/1.1 204 No Content\r\n\r\n");
+    assert_eq!(*seen.lock().unwrap(), ["X-B", "X-A"]);
+}
+
+#[test]
+fn redirected_request_signed() {
+    let redirect = b"HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n";
+
+    let mut send = Http11Send::new(request()).sign_with(|c| {
+        let base = format!("{} {}", c.method, c.target);
+        vec![("Signature".into(), format!("sig1=:{base}:"))]
+    });
+
+    let mut stream = StubStream::new(redirect);
+    let mut arg = None;
+
+    let url = loop {
+        match send.resume(arg.take()) {
+            Http11SendResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+            Http11SendResult::Redirect { url, .. } => break url,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    };
+
+    let request = HttpRequest::get(url).header("Host", "example.com");
+    let (_, written) = test_written(send.follow(request), b"HTTP/1.1 204 No Content\r\n\r\n");
+
+    let expected: &[u8] = b"GET /next HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Signature: sig1=:GET /next:\r\n\
+        \r\n";
+
+    assert_eq!(written, expected);
 }
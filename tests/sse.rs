@@ -0,0 +1,120 @@
+//! Tests for server-sent events (WHATWG HTML §9.2).
+//!
+//! All tests drive [`EventStreamRead`] against a pre-crafted
+//! in-memory buffer via [`stub::StubStream`]. No network connection
+//! is made.
+
+mod stub;
+
+use io_http::{
+    rfc9112::chunk::HttpChunksRead,
+    sse::event_stream::{EventStreamRead, EventStreamReadResult, SseEvent},
+};
+use io_socket::{coroutines::read::SocketRead, runtimes::std_stream::handle};
+
+use crate::stub::StubStream;
+
+fn test(mut read: EventStreamRead, body: &[u8]) -> Vec<SseEvent> {
+    let mut stream = StubStream::new(body);
+    let mut events = Vec::new();
+    let mut arg = None;
+
+    loop {
+        match read.resume(arg.take()) {
+            EventStreamReadResult::Event { event } => events.push(event),
+            EventStreamReadResult::Ok => return events,
+            EventStreamReadResult::Err { err } => panic!("unexpected error: {err}"),
+            EventStreamReadResult::Io { input } => arg = Some(handle(&mut stream, input).unwrap()),
+        }
+    }
+}
+
+fn test_identity(body: &[u8]) -> Vec<SseEvent> {
+    test(EventStreamRead::new(SocketRead::default()), body)
+}
+
+#[test]
+fn single_event_defaults_to_message() {
+    let events = test_identity(b"data: hello\n\n");
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event, "message");
+    assert_eq!(events[0].data, "hello");
+    assert_eq!(events[0].id, None);
+    assert_eq!(events[0].retry, None);
+}
+
+#[test]
+fn multi_line_data_joined_by_newlines() {
+    let events = test_identity(b"event: update\ndata: first\ndata: second\ndata\n\n");
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event, "update");
+    assert_eq!(events[0].data, "first\nsecond\n");
+}
+
+#[test]
+fn id_and_retry_fields() {
+    let body = b"id: 1\nretry: 3000\ndata: a\n\ndata: b\n\nid: 2\ndata: c\n\n";
+    let events = test_identity(body);
+
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].id.as_deref(), Some("1"));
+    assert_eq!(events[0].retry, Some(3000));
+    // the last event ID persists across events
+    assert_eq!(events[1].id.as_deref(), Some("1"));
+    assert_eq!(events[1].data, "b");
+    assert_eq!(events[2].id.as_deref(), Some("2"));
+}
+
+#[test]
+fn invalid_retry_ignored() {
+    let events = test_identity(b"retry: 3s\ndata: a\n\n");
+    assert_eq!(events[0].retry, None);
+}
+
+#[test]
+fn comments_ignored() {
+    let events = test_identity(b": keep-alive\n\n: comment\ndata: hello\n\n");
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].data, "hello");
+}
+
+#[test]
+fn event_without_data_not_dispatched() {
+    let events = test_identity(b"event: ping\n\ndata: hello\n\n");
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event, "message");
+}
+
+#[test]
+fn crlf_and_cr_line_endings() {
+    let events = test_identity(b"data: a\r\n\r\ndata: b\r\rdata:c\n\n");
+    let data: Vec<_> = events.iter().map(|e| e.data.as_str()).collect();
+    assert_eq!(data, ["a", "b", "c"]);
+}
+
+#[test]
+fn bom_stripped() {
+    let events = test_identity(b"\xEF\xBB\xBFdata: hello\n\n");
+    assert_eq!(events[0].data, "hello");
+}
+
+#[test]
+fn unterminated_event_discarded_on_eof() {
+    let events = test_identity(b"data: a\n\ndata: b\n");
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].data, "a");
+}
+
+#[test]
+fn chunked_event_stream() {
+    let body = b"8\r\ndata: he\r\n8\r\nllo\n\ndat\r\n6\r\na: x\n\n\r\n0\r\n\r\n";
+    let read = EventStreamRead::chunked(HttpChunksRead::new(SocketRead::default()));
+    let events = test(read, body);
+    let data: Vec<_> = events.iter().map(|e| e.data.as_str()).collect();
+    assert_eq!(data, ["hello", "x"]);
+}
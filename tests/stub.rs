@@ -7,32 +7,53 @@
 //! without a network connection.
 //!
 //! Reads drain bytes from the response buffer provided at
-//! construction; writes are silently discarded (the serialized
-//! request is not asserted).
+//! construction, optionally a few bytes at a time (see
+//! [`StubStream::read_size`]); writes are captured and can be
+//! inspected with [`StubStream::written`].
 
 use std::io::{Cursor, Read, Result, Write};
 
 /// An in-memory stream backed by a pre-crafted response buffer.
 pub struct StubStream<'a> {
     response: Cursor<&'a [u8]>,
+    written: Vec<u8>,
+    read_size: Option<usize>,
 }
 
 impl<'a> StubStream<'a> {
     pub fn new(response: &'a [u8]) -> Self {
         Self {
             response: Cursor::new(response),
+            written: Vec::new(),
+            read_size: None,
         }
     }
+
+    /// Limits the number of bytes returned by each read, to simulate
+    /// a response received in several network packets.
+    #[allow(dead_code)]
+    pub fn read_size(mut self, read_size: usize) -> Self {
+        self.read_size = Some(read_size);
+        self
+    }
+
+    /// Returns the bytes written so far (the serialized request).
+    #[allow(dead_code)]
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
 }
 
 impl Read for StubStream<'_> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        self.response.read(buf)
+        let len = self.read_size.unwrap_or(buf.len()).min(buf.len());
+        self.response.read(&mut buf[..len])
     }
 }
 
 impl Write for StubStream<'_> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.written.extend_from_slice(buf);
         Ok(buf.len())
     }
 